@@ -1,6 +1,5 @@
 //! An example that takes readings from the EC EZO chip in a loop.
 //!
-extern crate chrono;
 extern crate ezo_ec;
 extern crate failure;
 extern crate i2cdev;
@@ -8,10 +7,8 @@ extern crate i2cdev;
 use std::thread;
 use std::time::Duration;
 
-use chrono::{DateTime, Utc};
-
-use ezo_ec::command::{Command, OutputState, Reading, Sleep};
-use ezo_ec::response::{OutputStringStatus, ProbeReading};
+use ezo_ec::command::{Command, OutputState, Sleep};
+use ezo_ec::response::{LabeledReading, TimestampedReading};
 
 use failure::{Error, ResultExt};
 use i2cdev::linux::LinuxI2CDevice;
@@ -27,21 +24,23 @@ fn run() -> Result<(), Error> {
 
     let status = OutputState.run(&mut dev)?;
 
+    println!("timestamp,{}", status.csv_header());
+
     loop {
-        let ec_value = Reading.run(&mut dev)?;
+        let reading = TimestampedReading::capture(&mut dev)?;
 
-        let _out = _print_response(ec_value, &status)?;
+        _print_response(&reading)?;
 
-        let _sleep = Sleep.run(&mut dev)?;
+        Sleep.run(&mut dev)?;
 
         // Ideally, every 10 seconds, fine-tune this to your hardware.
         thread::sleep(Duration::from_millis(9_400));
     }
 }
 
-fn _print_response(reading: ProbeReading, status: &OutputStringStatus) -> Result<(), Error> {
-    let dt: DateTime<Utc> = Utc::now();
-    println!("{:?},{:?},{:?}", dt, reading, status,);
+fn _print_response(reading: &TimestampedReading) -> Result<(), Error> {
+    let labeled = LabeledReading::new(&reading.reading, &reading.status)?;
+    println!("{:?},{}", reading.timestamp, labeled.csv_row());
     Ok(())
 }
 
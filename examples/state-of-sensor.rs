@@ -5,12 +5,8 @@ extern crate failure;
 extern crate i2cdev;
 
 use ezo_ec::{
-    command::{
-        CalibrationState, Command, DeviceInformation, LedState, OutputState, Reading, Sleep, Status,
-    },
-    response::{
-        CalibrationStatus, DeviceInfo, DeviceStatus, LedStatus, OutputStringStatus, ProbeReading,
-    },
+    command::{snapshot, Command, LedState, Sleep},
+    response::{DeviceSnapshot, LedStatus},
 };
 use failure::{Error, ResultExt};
 use i2cdev::linux::LinuxI2CDevice;
@@ -23,25 +19,20 @@ fn run() -> Result<(), Error> {
     let mut dev =
         LinuxI2CDevice::new(&device_path, EZO_SENSOR_ADDR).context("Could not open I2C device")?;
 
-    let info: DeviceInfo = DeviceInformation.run(&mut dev)?;
-    println!("{:?}", info);
-
-    let status: DeviceStatus = Status.run(&mut dev)?;
-    println!("DeviceStatus: {:?}", status);
-
-    let calibration: CalibrationStatus = CalibrationState.run(&mut dev)?;
-    println!("CalibrationState: {:?}", calibration);
+    let state: DeviceSnapshot = snapshot(&mut dev)?;
+    println!("{:?}", state.info);
+    println!("DeviceStatus: {:?}", state.status);
+    println!("CalibrationState: {:?}", state.calibration);
+    println!("ProbeType: {:?}", state.probe);
+    println!("TemperatureCompensation: {:?}", state.compensation);
 
     let led_status: LedStatus = LedState.run(&mut dev)?;
     println!("LedState: {:?}", led_status);
 
-    let ec_value: ProbeReading = Reading.run(&mut dev)?;
-    println!("{:?}", ec_value);
-
-    let output_string: OutputStringStatus = OutputState.run(&mut dev)?;
-    println!("{:?}", output_string);
+    println!("{:?}", state.reading);
+    println!("{:?}", state.output);
 
-    let _sleep = Sleep.run(&mut dev)?;
+    Sleep.run(&mut dev)?;
     println!("Sleeping....");
 
     Ok(())
@@ -0,0 +1,436 @@
+//! Command exchanges with the chip over `embedded-hal` and
+//! `embedded-hal-async` I2C buses.
+//!
+//! Unlike the command runners, these need neither `std` nor `ezo_common`:
+//! they take a command string, the delay to wait for its response and the
+//! length to read back, and return the response string, to be parsed with
+//! the parsers in `response`. Delays are waited out with an injected timer.
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+use alloc::ffi::CString;
+use alloc::string::String;
+
+#[cfg(all(not(feature = "std"), any(feature = "embedded-hal", feature = "async")))]
+use super::core_compat::ResultExt;
+#[cfg(not(feature = "std"))]
+use super::core_compat::{response_code, string_from_response_data, ResponseCode};
+#[cfg(feature = "std")]
+use super::errors::{DeviceError, TruncatedResponse};
+use super::response::strip_framing;
+use super::{ErrorKind, EzoError};
+
+#[cfg(feature = "std")]
+use ezo_common::{response_code, string_from_response_data, ResponseCode};
+#[cfg(feature = "std")]
+use failure::Fail;
+#[cfg(all(feature = "std", any(feature = "embedded-hal", feature = "async")))]
+use failure::ResultExt;
+
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Read, Write},
+};
+
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+/// Maximum ascii-character response size + 2
+pub const MAX_DATA: usize = 401;
+
+/// Time to wait before writing a command again, when the first write fails,
+/// in ms.
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+const WRITE_RETRY_DELAY: u32 = 100;
+
+/// Writes `cmd` to the chip at `addr`, retrying once after 100ms, without
+/// reading a response back.
+#[cfg(feature = "embedded-hal")]
+pub fn write_command<I, D>(dev: &mut I, addr: u8, delay: &mut D, cmd: &str) -> Result<(), EzoError>
+where
+    I: Write,
+    D: DelayMs<u32>,
+{
+    let cmd = command_bytes(cmd)?;
+    if dev.write(addr, cmd.as_bytes_with_nul()).is_err() {
+        delay.delay_ms(WRITE_RETRY_DELAY);
+        dev.write(addr, cmd.as_bytes_with_nul())
+            .map_err(|_| ErrorKind::I2CWrite)?;
+    }
+    Ok(())
+}
+
+/// Writes `cmd` to the chip at `addr`, waits `delay_ms` milliseconds with
+/// `delay`, and returns its response string, read from at most `len` bytes.
+#[cfg(feature = "embedded-hal")]
+pub fn exchange<I, D>(
+    dev: &mut I,
+    addr: u8,
+    delay: &mut D,
+    cmd: &str,
+    delay_ms: u64,
+    len: usize,
+) -> Result<String, EzoError>
+where
+    I: Write + Read,
+    D: DelayMs<u32>,
+{
+    trace_exchange!(
+        "writing {:?} to {:#04x}, then waiting {} ms",
+        cmd,
+        addr,
+        delay_ms
+    );
+    write_command(dev, addr, delay, cmd)?;
+    delay.delay_ms(delay_ms as u32);
+    let mut data_buffer = [0u8; MAX_DATA];
+    let data = &mut data_buffer[..response_len(len)];
+    dev.read(addr, data).map_err(|_| ErrorKind::I2CRead)?;
+    trace_exchange!("read {:?}", raw_response(data));
+    response_from_data(data)
+}
+
+/// Like `exchange`, over an `embedded-hal-async` I2C bus. The response delay
+/// is awaited, so no thread is blocked while the chip processes the command.
+#[cfg(feature = "async")]
+pub async fn exchange_async<I: I2c, D: DelayNs>(
+    dev: &mut I,
+    addr: u8,
+    delay: &mut D,
+    cmd: &str,
+    delay_ms: u64,
+    len: usize,
+) -> Result<String, EzoError> {
+    trace_exchange!(
+        "writing {:?} to {:#04x}, then waiting {} ms",
+        cmd,
+        addr,
+        delay_ms
+    );
+    let cmd_string = command_bytes(cmd)?;
+    if dev
+        .write(addr, cmd_string.as_bytes_with_nul())
+        .await
+        .is_err()
+    {
+        delay.delay_ms(WRITE_RETRY_DELAY).await;
+        dev.write(addr, cmd_string.as_bytes_with_nul())
+            .await
+            .map_err(|_| ErrorKind::I2CWrite)?;
+    }
+    delay.delay_ms(delay_ms as u32).await;
+    let mut data_buffer = [0u8; MAX_DATA];
+    let data = &mut data_buffer[..response_len(len)];
+    dev.read(addr, data).await.map_err(|_| ErrorKind::I2CRead)?;
+    trace_exchange!("read {:?}", raw_response(data));
+    response_from_data(data)
+}
+
+/// The command as sent on the bus, nul terminator included. Fails with
+/// `ErrorKind::CommandParse` when it holds a nul itself.
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+fn command_bytes(cmd: &str) -> Result<CString, EzoError> {
+    let cmd = CString::new(cmd).context(ErrorKind::CommandParse)?;
+    Ok(cmd)
+}
+
+/// Bounds a requested response length to what a response can span: at
+/// least a response code and a nul, and at most `MAX_DATA`.
+pub(crate) fn response_len(len: usize) -> usize {
+    len.clamp(2, MAX_DATA)
+}
+
+/// Bytes read back from the chip, up to and including the nul terminator.
+#[cfg(feature = "logging")]
+pub(crate) fn raw_response(data: &[u8]) -> &[u8] {
+    match data.iter().position(|&x| x == 0) {
+        Some(len) => &data[..=len],
+        None => data,
+    }
+}
+
+/// Wraps the response `code` returned by the chip under the given `kind`,
+/// so that it can be recovered with `errors::device_error`.
+#[cfg(feature = "std")]
+fn device_error_context(code: u8, kind: ErrorKind) -> EzoError {
+    DeviceError { code }.context(kind).into()
+}
+
+/// Without `std`, errors only carry their kind, not the response code.
+#[cfg(not(feature = "std"))]
+fn device_error_context(_code: u8, kind: ErrorKind) -> EzoError {
+    kind.into()
+}
+
+/// Error for a response that stops being ASCII before its nul terminator,
+/// carrying the ASCII text read up to that point.
+#[cfg(feature = "std")]
+fn truncated_response_error(data: &[u8]) -> EzoError {
+    let end = data
+        .iter()
+        .position(|&x| x == 0 || !x.is_ascii())
+        .unwrap_or(data.len());
+    let partial = String::from_utf8_lossy(&data[..end]).into_owned();
+    TruncatedResponse { partial }
+        .context(ErrorKind::MalformedResponse)
+        .into()
+}
+
+/// Without `std`, errors only carry their kind, not the partial response.
+#[cfg(not(feature = "std"))]
+fn truncated_response_error(_data: &[u8]) -> EzoError {
+    ErrorKind::MalformedResponse.into()
+}
+
+/// Decodes the bytes read back from the chip into its response string.
+pub(crate) fn response_from_data(data: &[u8]) -> Result<String, EzoError> {
+    match response_code(data[0]) {
+        ResponseCode::Success => match data.iter().position(|&x| x == 0) {
+            Some(len) if data[1..len].is_ascii() => {
+                let resp = string_from_response_data(&data[1..=len])?;
+                strip_framing(&resp).map(String::from)
+            }
+            _ => Err(truncated_response_error(&data[1..])),
+        },
+        ResponseCode::Pending => Err(device_error_context(data[0], ErrorKind::PendingResponse)),
+        ResponseCode::DeviceError => Err(device_error_context(
+            data[0],
+            ErrorKind::DeviceErrorResponse,
+        )),
+        ResponseCode::NoDataExpected => Err(device_error_context(
+            data[0],
+            ErrorKind::NoDataExpectedResponse,
+        )),
+        ResponseCode::UnknownError => Err(ErrorKind::MalformedResponse.into()),
+    }
+}
+
+#[cfg(all(test, any(feature = "embedded-hal", feature = "async")))]
+mod tests {
+    use super::*;
+    use alloc::collections::VecDeque;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    #[cfg(feature = "async")]
+    use core::future::Future;
+    #[cfg(feature = "async")]
+    use core::task::{Context, Poll, Waker};
+
+    #[cfg(feature = "async")]
+    use embedded_hal_async::i2c::{ErrorKind as BusError, ErrorType, Operation};
+
+    /// I2C bus that records the bytes written to it and answers reads with
+    /// queued responses. The first `failed_writes` writes fail.
+    #[derive(Default)]
+    struct MockBus {
+        written: Vec<Vec<u8>>,
+        responses: VecDeque<Vec<u8>>,
+        failed_writes: usize,
+    }
+
+    impl MockBus {
+        fn answering(response: &[u8]) -> MockBus {
+            let mut bus = MockBus::default();
+            bus.responses.push_back(response.to_vec());
+            bus
+        }
+
+        fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), ()> {
+            if self.failed_writes > 0 {
+                self.failed_writes -= 1;
+                return Err(());
+            }
+            self.written.push(bytes.to_vec());
+            Ok(())
+        }
+
+        fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), ()> {
+            let response = self.responses.pop_front().ok_or(())?;
+            let len = response.len().min(buffer.len());
+            buffer.iter_mut().for_each(|byte| *byte = 0);
+            buffer[..len].copy_from_slice(&response[..len]);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    impl Write for MockBus {
+        type Error = ();
+
+        fn write(&mut self, _addr: u8, bytes: &[u8]) -> Result<(), ()> {
+            self.write_bytes(bytes)
+        }
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    impl Read for MockBus {
+        type Error = ();
+
+        fn read(&mut self, _addr: u8, buffer: &mut [u8]) -> Result<(), ()> {
+            self.read_bytes(buffer)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl ErrorType for MockBus {
+        type Error = BusError;
+    }
+
+    #[cfg(feature = "async")]
+    impl I2c for MockBus {
+        async fn transaction(
+            &mut self,
+            _address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), BusError> {
+            for operation in operations.iter_mut() {
+                match operation {
+                    Operation::Write(bytes) => self.write_bytes(bytes),
+                    Operation::Read(buffer) => self.read_bytes(buffer),
+                }
+                .map_err(|_| BusError::Other)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Timer that only adds up the time it was asked to wait.
+    #[derive(Default)]
+    struct MockTimer {
+        waited_ns: u64,
+    }
+
+    impl MockTimer {
+        fn waited_ms(&self) -> u64 {
+            self.waited_ns / 1_000_000
+        }
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    impl DelayMs<u32> for MockTimer {
+        fn delay_ms(&mut self, ms: u32) {
+            self.waited_ns += u64::from(ms) * 1_000_000;
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl DelayNs for MockTimer {
+        async fn delay_ns(&mut self, ns: u32) {
+            self.waited_ns += u64::from(ns);
+        }
+    }
+
+    /// Polls `future` to completion. The mocks never return `Pending`.
+    #[cfg(feature = "async")]
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = core::pin::pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_response_length_to_code_and_nul_up_to_max_data() {
+        assert_eq!(response_len(0), 2);
+        assert_eq!(response_len(16), 16);
+        assert_eq!(response_len(1024), MAX_DATA);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal")]
+    fn exchanges_over_blocking_bus_waiting_with_injected_timer() {
+        let mut dev = MockBus::answering(b"\x0112.5,0.0\0");
+        dev.failed_writes = 1;
+        let mut timer = MockTimer::default();
+        let resp = exchange(&mut dev, 0x64, &mut timer, "R", 600, MAX_DATA).unwrap();
+        assert_eq!(resp, "12.5,0.0");
+        assert_eq!(dev.written, vec![b"R\0".to_vec()]);
+        assert_eq!(timer.waited_ms(), 700);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal")]
+    fn fails_blocking_exchange_on_write_and_read_errors() {
+        let mut dev = MockBus {
+            failed_writes: 2,
+            ..MockBus::default()
+        };
+        let mut timer = MockTimer::default();
+        let error = exchange(&mut dev, 0x64, &mut timer, "R", 600, MAX_DATA).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::I2CWrite);
+
+        let mut dev = MockBus::default();
+        let error = exchange(&mut dev, 0x64, &mut timer, "R", 600, MAX_DATA).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::I2CRead);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn exchanges_over_async_bus_awaiting_injected_timer() {
+        let mut dev = MockBus::answering(b"\x01?K,0.1\0");
+        dev.failed_writes = 1;
+        let mut timer = MockTimer::default();
+        let resp = block_on(exchange_async(
+            &mut dev, 0x64, &mut timer, "K,?", 300, MAX_DATA,
+        ))
+        .unwrap();
+        assert_eq!(resp, "?K,0.1");
+        assert_eq!(dev.written, vec![b"K,?\0".to_vec()]);
+        assert_eq!(timer.waited_ms(), 400);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn fails_async_exchange_with_kind_of_response_code() {
+        for &(code, kind) in &[
+            (254, ErrorKind::PendingResponse),
+            (2, ErrorKind::DeviceErrorResponse),
+            (255, ErrorKind::NoDataExpectedResponse),
+        ] {
+            let mut dev = MockBus::answering(&[code, 0]);
+            let mut timer = MockTimer::default();
+            let error = block_on(exchange_async(
+                &mut dev, 0x64, &mut timer, "R", 600, MAX_DATA,
+            ))
+            .unwrap_err();
+            assert_eq!(error.kind(), kind);
+        }
+
+        let mut dev = MockBus::default();
+        let mut timer = MockTimer::default();
+        let error = block_on(exchange_async(
+            &mut dev, 0x64, &mut timer, "R", 600, MAX_DATA,
+        ))
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::I2CRead);
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "async"))]
+    fn runs_commands_asynchronously_over_mock_bus() {
+        use crate::command::{run_async, CalibrationDry, CalibrationState, Reading};
+        use crate::errors::{device_error, failed_command};
+        use crate::response::ProbeReading;
+
+        let mut dev = MockBus::answering(b"\x0112.5,0.0\0");
+        let mut timer = MockTimer::default();
+        let reading = block_on(run_async(&Reading, &mut dev, 0x64, &mut timer)).unwrap();
+        assert_eq!(reading, ProbeReading::parse("12.5,0.0").unwrap());
+        assert_eq!(dev.written, vec![b"R\0".to_vec()]);
+        assert_eq!(timer.waited_ms(), 600);
+
+        let mut dev = MockBus::answering(&[255, 0]);
+        let mut timer = MockTimer::default();
+        block_on(run_async(&CalibrationDry, &mut dev, 0x64, &mut timer)).unwrap();
+        assert_eq!(dev.written, vec![b"CAL,DRY\0".to_vec()]);
+        assert_eq!(timer.waited_ms(), 800);
+
+        let mut dev = MockBus::answering(&[2, 0]);
+        let mut timer = MockTimer::default();
+        let error = block_on(run_async(&CalibrationState, &mut dev, 0x64, &mut timer)).unwrap_err();
+        assert_eq!(device_error(&error).map(|error| error.code), Some(2));
+        assert_eq!(failed_command(&error), Some("CAL,?".to_string()));
+    }
+}
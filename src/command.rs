@@ -1,439 +1,2499 @@
 //! I2C Commands for EC EZO Chip.
 //!
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
 use std::str::FromStr;
 use std::thread;
-use std::time::Duration;
-
+use std::time::{Duration, Instant};
+
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+use super::bus;
+#[cfg(feature = "logging")]
+use super::bus::raw_response;
+use super::bus::{response_from_data, response_len};
+use super::errors::{
+    failed_command, CommandFailed, DeviceTimeout, SequenceError, SettingMismatch,
+    UnsupportedOutput, ValueOutOfRange,
+};
 use super::response::{
-    CalibrationStatus, CompensationValue, OutputStringStatus, ProbeReading, ProbeType,
+    AnyResponse, CalibrationStatus, Celsius, CompensationValue, DeviceSnapshot, LabeledReading,
+    OutputStringStatus, ProbeMetric, ProbeReading, ProbeType, RawReading, ResponseCodeStatus,
+    ResponseType, TdsFactor, TemperatureScale, TypedResponse,
 };
-use super::{ErrorKind, EzoError};
+use super::response::{DeviceInfo, DeviceStatus};
+#[cfg(feature = "chrono")]
+use super::response::{ReadingContext, TimestampedReading};
+#[cfg(feature = "embedded-hal")]
+use super::transport::{HalTransport, ThreadDelay};
+use super::{transport, ErrorKind, EzoError};
 
-use failure::ResultExt;
+use failure::{Fail, ResultExt};
 
 use ezo_common::{
-    response::ResponseStatus, response_code, string_from_response_data, write_to_ezo, ResponseCode,
+    response::{ExportedInfo, ResponseStatus},
+    response_code, string_from_response_data, write_to_ezo, ResponseCode,
 };
 
+#[cfg(feature = "chrono")]
+use chrono::Utc;
+
 use i2cdev::core::I2CDevice;
 use i2cdev::linux::LinuxI2CDevice;
 
-/// Maximum ascii-character response size + 2
-pub const MAX_DATA: usize = 401;
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::blocking::i2c::{Read, Write};
+
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+pub use super::bus::MAX_DATA;
+
+/// Bytes read back for commands that are only acknowledged: the response
+/// code, room for `*OK` framing, and the nul terminator.
+pub const ACK_RESPONSE_LEN: usize = 8;
+
+/// Longest calibration string the chip exports, and accepts back with
+/// `Import`, per line.
+pub const EXPORT_CHUNK_LEN: usize = 12;
+
+/// Time between attempts to reach the chip while it restarts, in ms.
+const RESTART_POLL_INTERVAL: u64 = 500;
+
+/// Time the chip needs to store a calibration point before it takes the
+/// next command, in ms.
+const CALIBRATION_COOLDOWN: u64 = 300;
+
+/// Lowest temperature accepted for compensation, in °C.
+const ABSOLUTE_ZERO: f64 = -273.15;
+
+pub use ezo_common::command::{
+    Baud, CalibrationClear, DeviceAddress, DeviceInformation, Export, ExportInfo, Factory, Find,
+    Import, LedOff, LedOn, LedState, ProtocolLockDisable, ProtocolLockEnable, ProtocolLockState,
+    Sleep, Status,
+};
+/// I2C command for the EZO chip.
+pub use ezo_common::Command;
+
+/// EC-specific behaviour shared by every command, independent of the bus
+/// used to talk to the chip.
+pub trait EcCommand: Command {
+    /// Parses the response string returned by the chip for this command.
+    fn parse_response(&self, response: &str) -> Result<Self::Response, EzoError>;
+
+    /// Number of bytes to read back for the response, including the
+    /// response code and the nul terminator. Capped at `MAX_DATA`.
+    fn max_response_len(&self) -> usize {
+        MAX_DATA
+    }
+
+    /// Whether the chip's `255` "no data" response code counts as success.
+    ///
+    /// Setters have no payload, so the code only confirms them. Queries have
+    /// to answer with data, and fail with `ErrorKind::NoDataExpectedResponse`.
+    ///
+    /// Honored by every runner in this crate, and by `Command::run` for the
+    /// commands defined here. The `Command::run` of the setters defined in
+    /// `ezo_common`, such as `Find`, always fails on the code; run them with
+    /// `run_on`, which takes a `LinuxI2CDevice` too.
+    fn accepts_no_data(&self) -> bool {
+        false
+    }
+
+    /// Time to wait after the response, before the next command is sent,
+    /// e.g. while the chip writes a setting to its EEPROM. Unlike the
+    /// response delay, it is only honored by the sequence runners.
+    fn cooldown(&self) -> Duration {
+        Duration::from_millis(0)
+    }
+
+    /// Kind of response the command yields, known before running it.
+    fn expected_response_kind(&self) -> ResponseType
+    where
+        Self::Response: TypedResponse,
+    {
+        <Self::Response as TypedResponse>::RESPONSE_TYPE
+    }
+
+    /// Runs the command over any `EzoTransport`, e.g. a USB-to-I2C bridge.
+    fn run_on<T: transport::EzoTransport>(
+        &self,
+        transport: &mut T,
+    ) -> Result<Self::Response, EzoError> {
+        let command = self.get_command_string();
+        let resp = exchange(
+            transport,
+            &command,
+            self.get_delay(),
+            self.max_response_len(),
+        );
+        accept_no_data(resp, self.accepts_no_data())
+            .and_then(|resp| self.parse_response(&resp))
+            .map_err(|error| CommandFailed::wrap(&command, error))
+    }
+
+    /// Runs the command over an `embedded-hal` I2C bus, talking to the chip
+    /// at address `addr`.
+    #[cfg(feature = "embedded-hal")]
+    fn run_hal<I: Write + Read>(&self, dev: &mut I, addr: u8) -> Result<Self::Response, EzoError> {
+        self.run_on(&mut HalTransport::new(dev, addr))
+    }
+
+    /// Runs the command on the chip, and returns its trimmed response string
+    /// without parsing it.
+    ///
+    /// Useful for diagnostics, or for responses the typed parsers don't cover.
+    fn run_raw(&self, dev: &mut LinuxI2CDevice) -> Result<String, EzoError> {
+        self.run_raw_on(dev)
+    }
+
+    /// Like `run_raw`, over any `EzoTransport`.
+    fn run_raw_on<T: transport::EzoTransport>(
+        &self,
+        transport: &mut T,
+    ) -> Result<String, EzoError> {
+        let command = self.get_command_string();
+        let resp = exchange(
+            transport,
+            &command,
+            self.get_delay(),
+            self.max_response_len(),
+        );
+        accept_no_data(resp, self.accepts_no_data())
+            .map(|resp| resp.trim().to_string())
+            .map_err(|error| CommandFailed::wrap(&command, error))
+    }
+
+    /// Like `run_raw`, over an `embedded-hal` I2C bus.
+    #[cfg(feature = "embedded-hal")]
+    fn run_raw_hal<I: Write + Read>(&self, dev: &mut I, addr: u8) -> Result<String, EzoError> {
+        self.run_raw_on(&mut HalTransport::new(dev, addr))
+    }
+
+    /// Wraps the command so that it waits `ms` milliseconds for the response,
+    /// instead of its default delay.
+    ///
+    /// The defaults are the datasheet's worst-case processing times. Waiting
+    /// less than the chip needs yields `ErrorKind::PendingResponse` errors.
+    fn with_delay(self, ms: u64) -> DelayOverride<Self>
+    where
+        Self: Sized,
+    {
+        DelayOverride {
+            command: self,
+            delay: ms,
+        }
+    }
+}
+
+/// Command that waits a custom delay for its response. Built with
+/// `EcCommand::with_delay`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelayOverride<C> {
+    pub command: C,
+    pub delay: u64,
+}
+
+impl<C: EcCommand<Error = EzoError>> Command for DelayOverride<C> {
+    type Error = EzoError;
+    type Response = C::Response;
+
+    fn get_command_string(&self) -> String {
+        self.command.get_command_string()
+    }
+
+    fn get_delay(&self) -> u64 {
+        self.delay
+    }
+
+    fn run(&self, dev: &mut LinuxI2CDevice) -> Result<C::Response, EzoError> {
+        let command = self.get_command_string();
+        let resp = exchange(dev, &command, self.get_delay(), self.max_response_len());
+        accept_no_data(resp, self.accepts_no_data())
+            .and_then(|resp| self.parse_response(&resp))
+            .map_err(|error| CommandFailed::wrap(&command, error))
+    }
+}
+
+impl<C: EcCommand<Error = EzoError>> EcCommand for DelayOverride<C> {
+    fn parse_response(&self, response: &str) -> Result<C::Response, EzoError> {
+        self.command.parse_response(response)
+    }
+
+    fn max_response_len(&self) -> usize {
+        self.command.max_response_len()
+    }
+
+    fn accepts_no_data(&self) -> bool {
+        self.command.accepts_no_data()
+    }
+
+    fn cooldown(&self) -> Duration {
+        self.command.cooldown()
+    }
+}
+
+/// Setter whose value can be read back with a query command.
+pub trait VerifiedSetter: EcCommand<Error = EzoError> {
+    type Query: EcCommand<Error = EzoError>;
+
+    /// The command that reads the setting back.
+    fn query(&self) -> Self::Query;
+
+    /// Checks that the response to `query` shows the value that was set.
+    /// Otherwise, fails with `ErrorKind::DeviceErrorResponse`, caused by a
+    /// `SettingMismatch`.
+    fn verify(&self, response: &<Self::Query as Command>::Response) -> Result<(), EzoError>;
+
+    /// Runs the setter, then reads the setting back and verifies it. This
+    /// catches values that silently failed to stick.
+    fn run_verified(&self, dev: &mut LinuxI2CDevice) -> Result<(), EzoError> {
+        self.run(dev)?;
+        let response = self.query().run(dev)?;
+        self.verify(&response)
+    }
+
+    /// Like `run_verified`, over any `EzoTransport`.
+    fn run_verified_on<T: transport::EzoTransport>(
+        &self,
+        transport: &mut T,
+    ) -> Result<(), EzoError> {
+        self.run_on(transport)?;
+        let response = self.query().run_on(transport)?;
+        self.verify(&response)
+    }
+
+    /// Like `run_verified`, over an `embedded-hal` I2C bus.
+    #[cfg(feature = "embedded-hal")]
+    fn run_verified_hal<I: Write + Read>(&self, dev: &mut I, addr: u8) -> Result<(), EzoError> {
+        self.run_verified_on(&mut HalTransport::new(dev, addr))
+    }
+}
+
+/// Object-safe view of an `EcCommand`, so that commands with different
+/// response types can be run one after the other.
+///
+/// Boxed commands can be cloned, so a sequence kept in a
+/// `Vec<Box<dyn DynCommand>>` can be replayed, e.g. after a device reset.
+pub trait DynCommand {
+    fn command_string(&self) -> String;
+    fn delay(&self) -> u64;
+    fn read_len(&self) -> usize;
+    fn cooldown_ms(&self) -> u64;
+    fn response_type(&self) -> ResponseType;
+    fn box_clone(&self) -> Box<dyn DynCommand>;
+}
+
+impl<C> DynCommand for C
+where
+    C: EcCommand + Clone + 'static,
+    C::Response: TypedResponse,
+{
+    fn command_string(&self) -> String {
+        self.get_command_string()
+    }
+
+    fn delay(&self) -> u64 {
+        self.get_delay()
+    }
+
+    fn read_len(&self) -> usize {
+        self.max_response_len()
+    }
+
+    fn cooldown_ms(&self) -> u64 {
+        self.cooldown().as_millis() as u64
+    }
+
+    fn response_type(&self) -> ResponseType {
+        self.expected_response_kind()
+    }
+
+    fn box_clone(&self) -> Box<dyn DynCommand> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn DynCommand> {
+    fn clone(&self) -> Box<dyn DynCommand> {
+        self.box_clone()
+    }
+}
+
+/// Runs a command through dynamic dispatch, and parses its response into the
+/// `AnyResponse` variant matching its prefix.
+///
+/// Only responses of EC commands are recognized; e.g. the `?I,...` response
+/// to `DeviceInformation` fails with `ErrorKind::ResponseParse`.
+pub fn run_dyn(dev: &mut LinuxI2CDevice, cmd: &dyn DynCommand) -> Result<AnyResponse, EzoError> {
+    run_dyn_delayed(dev, cmd, cmd.delay())
+}
+
+/// Like `run_dyn`, waiting `delay` milliseconds instead of the command's
+/// own delay.
+pub(crate) fn run_dyn_delayed(
+    dev: &mut LinuxI2CDevice,
+    cmd: &dyn DynCommand,
+    delay: u64,
+) -> Result<AnyResponse, EzoError> {
+    run_dyn_delayed_on(dev, cmd, delay)
+}
+
+/// Like `run_dyn`, over any `EzoTransport`.
+pub fn run_dyn_on<T: transport::EzoTransport>(
+    transport: &mut T,
+    cmd: &dyn DynCommand,
+) -> Result<AnyResponse, EzoError> {
+    run_dyn_delayed_on(transport, cmd, cmd.delay())
+}
+
+/// Like `run_dyn_on`, waiting `delay` milliseconds instead of the command's
+/// own delay.
+pub(crate) fn run_dyn_delayed_on<T: transport::EzoTransport>(
+    transport: &mut T,
+    cmd: &dyn DynCommand,
+    delay: u64,
+) -> Result<AnyResponse, EzoError> {
+    let command = cmd.command_string();
+    let resp = exchange(transport, &command, delay, cmd.read_len());
+    accept_no_data(resp, cmd.response_type() == ResponseType::Ack)
+        .and_then(|resp| AnyResponse::parse(&resp))
+        .map_err(|error| CommandFailed::wrap(&command, error))
+}
+
+/// Like `run_dyn`, over an `embedded-hal` I2C bus.
+#[cfg(feature = "embedded-hal")]
+pub fn run_dyn_hal<I: Write + Read>(
+    dev: &mut I,
+    addr: u8,
+    cmd: &dyn DynCommand,
+) -> Result<AnyResponse, EzoError> {
+    run_dyn_hal_delayed(dev, addr, cmd, cmd.delay())
+}
+
+/// Like `run_dyn_hal`, waiting `delay` milliseconds instead of the
+/// command's own delay.
+#[cfg(feature = "embedded-hal")]
+pub(crate) fn run_dyn_hal_delayed<I: Write + Read>(
+    dev: &mut I,
+    addr: u8,
+    cmd: &dyn DynCommand,
+    delay: u64,
+) -> Result<AnyResponse, EzoError> {
+    trace_exchange!("talking to the chip at {:#04x}", addr);
+    run_dyn_delayed_on(&mut HalTransport::new(dev, addr), cmd, delay)
+}
+
+/// Runs `cmds` in order, stopping at the first one the chip doesn't
+/// acknowledge. Typed responses are discarded.
+///
+/// Each command's `cooldown` is waited before the next command is sent.
+pub fn run_sequence(
+    dev: &mut LinuxI2CDevice,
+    cmds: &[&dyn DynCommand],
+) -> Result<(), SequenceError> {
+    run_sequence_on(dev, cmds)
+}
+
+/// Like `run_sequence`, over any `EzoTransport`.
+pub fn run_sequence_on<T: transport::EzoTransport>(
+    transport: &mut T,
+    cmds: &[&dyn DynCommand],
+) -> Result<(), SequenceError> {
+    for (index, cmd) in cmds.iter().enumerate() {
+        let command = cmd.command_string();
+        let resp = exchange(transport, &command, cmd.delay(), cmd.read_len());
+        if let Err(error) = accept_no_data(resp, cmd.response_type() == ResponseType::Ack) {
+            return Err(SequenceError {
+                index,
+                command,
+                error,
+            });
+        }
+        if cmd.cooldown_ms() > 0 && index + 1 < cmds.len() {
+            transport.delay(cmd.cooldown_ms());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "chrono")]
+impl TimestampedReading {
+    /// Queries the output configuration, then takes a reading stamped with
+    /// the current time.
+    pub fn capture(dev: &mut LinuxI2CDevice) -> Result<TimestampedReading, EzoError> {
+        let status = OutputState.run(dev)?;
+        let reading = Reading.run(dev)?;
+        Ok(TimestampedReading {
+            timestamp: Utc::now(),
+            reading,
+            status,
+            context: ReadingContext::new(),
+        })
+    }
+}
+
+/// A reading paired with the temperature the chip compensates it for.
+///
+/// The temperature is queried after the reading, so its result is kept
+/// separately: a failed `T,?` doesn't discard a good reading.
+#[derive(Debug)]
+pub struct ReadingWithCompensation {
+    pub reading: ProbeReading,
+    pub compensation: Result<CompensationValue, EzoError>,
+}
+
+impl ReadingWithCompensation {
+    /// Issues `R`, then `T,?`.
+    pub fn capture(dev: &mut LinuxI2CDevice) -> Result<ReadingWithCompensation, EzoError> {
+        let reading = Reading.run(dev)?;
+        let compensation = CompensatedTemperatureValue.run(dev);
+        Ok(ReadingWithCompensation {
+            reading,
+            compensation,
+        })
+    }
+
+    /// Issues `R`, then `T,?`, over any `EzoTransport`.
+    pub fn capture_on<T: transport::EzoTransport>(
+        transport: &mut T,
+    ) -> Result<ReadingWithCompensation, EzoError> {
+        let reading = Reading.run_on(transport)?;
+        let compensation = CompensatedTemperatureValue.run_on(transport);
+        Ok(ReadingWithCompensation {
+            reading,
+            compensation,
+        })
+    }
+
+    /// Issues `R`, then `T,?`, over an `embedded-hal` I2C bus.
+    #[cfg(feature = "embedded-hal")]
+    pub fn capture_hal<I: Write + Read>(
+        dev: &mut I,
+        addr: u8,
+    ) -> Result<ReadingWithCompensation, EzoError> {
+        ReadingWithCompensation::capture_on(&mut HalTransport::new(dev, addr))
+    }
+
+    /// The reading and temperature as a pair, failing if the temperature
+    /// query failed.
+    pub fn into_pair(self) -> Result<(ProbeReading, CompensationValue), EzoError> {
+        Ok((self.reading, self.compensation?))
+    }
+}
+
+/// Polls the chip over an `embedded-hal` I2C bus for readings taken
+/// `interval` apart.
+///
+/// A failed reading is returned as an error for that item only; the next
+/// call polls again.
+#[cfg(feature = "embedded-hal")]
+pub struct ReadingStream<'a, I> {
+    dev: &'a mut I,
+    addr: u8,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<'a, I: Write + Read> ReadingStream<'a, I> {
+    pub fn new(dev: &'a mut I, addr: u8, interval: Duration) -> ReadingStream<'a, I> {
+        ReadingStream {
+            dev,
+            addr,
+            interval,
+            last: None,
+        }
+    }
+
+    /// Waits until `interval` has passed since the previous reading was
+    /// requested, then issues `R`. The first reading is requested right away.
+    pub fn next_reading(&mut self) -> Result<ProbeReading, EzoError> {
+        if let Some(last) = self.last {
+            if let Some(wait) = self.interval.checked_sub(last.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        self.last = Some(Instant::now());
+        Reading.run_hal(self.dev, self.addr)
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<I: Write + Read> Iterator for ReadingStream<'_, I> {
+    type Item = Result<ProbeReading, EzoError>;
+
+    /// Never returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_reading())
+    }
+}
+
+/// Queries device information, status, calibration, probe type, output
+/// configuration and temperature compensation, then takes a reading.
+///
+/// A failed query is recorded in its field, and the rest go on. Only when
+/// every query fails, e.g. because the chip isn't on the bus, is the last
+/// error returned.
+pub fn snapshot(dev: &mut LinuxI2CDevice) -> Result<DeviceSnapshot, EzoError> {
+    let mut error = None;
+    let snapshot = DeviceSnapshot {
+        info: snapshot_field(DeviceInformation.run(dev), &mut error),
+        status: snapshot_field(Status.run(dev), &mut error),
+        calibration: snapshot_field(CalibrationState.run(dev), &mut error),
+        probe: snapshot_field(ProbeTypeState.run(dev), &mut error),
+        output: snapshot_field(OutputState.run(dev), &mut error),
+        compensation: snapshot_field(CompensatedTemperatureValue.run(dev), &mut error),
+        reading: snapshot_field(Reading.run(dev), &mut error),
+    };
+    finish_snapshot(snapshot, error)
+}
+
+/// Like `snapshot`, over any `EzoTransport`.
+pub fn snapshot_on<T: transport::EzoTransport>(
+    transport: &mut T,
+) -> Result<DeviceSnapshot, EzoError> {
+    let mut error = None;
+    let info = run_common_on(transport, &DeviceInformation, DeviceInfo::parse);
+    let status = run_common_on(transport, &Status, DeviceStatus::parse);
+    let snapshot = DeviceSnapshot {
+        info: snapshot_field(info, &mut error),
+        status: snapshot_field(status, &mut error),
+        calibration: snapshot_field(CalibrationState.run_on(transport), &mut error),
+        probe: snapshot_field(ProbeTypeState.run_on(transport), &mut error),
+        output: snapshot_field(OutputState.run_on(transport), &mut error),
+        compensation: snapshot_field(CompensatedTemperatureValue.run_on(transport), &mut error),
+        reading: snapshot_field(Reading.run_on(transport), &mut error),
+    };
+    finish_snapshot(snapshot, error)
+}
+
+/// Like `snapshot`, over an `embedded-hal` I2C bus.
+#[cfg(feature = "embedded-hal")]
+pub fn snapshot_hal<I: Write + Read>(dev: &mut I, addr: u8) -> Result<DeviceSnapshot, EzoError> {
+    snapshot_on(&mut HalTransport::new(dev, addr))
+}
+
+/// Keeps the error message of a failed snapshot query, naming the failed
+/// command, and the error itself in `error`.
+fn snapshot_field<T>(
+    result: Result<T, EzoError>,
+    error: &mut Option<EzoError>,
+) -> Result<T, String> {
+    result.map_err(|e| {
+        let message = match failed_command(&e) {
+            Some(command) => format!("{} to '{}'", e, command),
+            None => e.to_string(),
+        };
+        *error = Some(e);
+        message
+    })
+}
+
+fn finish_snapshot(
+    snapshot: DeviceSnapshot,
+    error: Option<EzoError>,
+) -> Result<DeviceSnapshot, EzoError> {
+    let nothing_answered = snapshot.info.is_err()
+        && snapshot.status.is_err()
+        && snapshot.calibration.is_err()
+        && snapshot.probe.is_err()
+        && snapshot.output.is_err()
+        && snapshot.compensation.is_err()
+        && snapshot.reading.is_err();
+    match error {
+        Some(error) if nothing_answered => Err(error),
+        _ => Ok(snapshot),
+    }
+}
+
+/// Takes a reading, and keeps the response string along with its parsed
+/// values, from a single `R` command.
+pub fn read_raw(dev: &mut LinuxI2CDevice) -> Result<RawReading, EzoError> {
+    read_raw_on(dev)
+}
+
+/// Like `read_raw`, over any `EzoTransport`.
+pub fn read_raw_on<T: transport::EzoTransport>(transport: &mut T) -> Result<RawReading, EzoError> {
+    let raw = Reading.run_raw_on(transport)?;
+    RawReading::parse(&raw)
+        .map_err(|error| CommandFailed::wrap(&Reading.get_command_string(), error))
+}
+
+/// Like `read_raw`, over an `embedded-hal` I2C bus.
+#[cfg(feature = "embedded-hal")]
+pub fn read_raw_hal<I: Write + Read>(dev: &mut I, addr: u8) -> Result<RawReading, EzoError> {
+    read_raw_on(&mut HalTransport::new(dev, addr))
+}
+
+/// Takes a reading with salinity as the only parameter in the output string,
+/// and returns its value.
+///
+/// The current output configuration is queried, switched to salinity only,
+/// and restored once the reading is taken, even if the reading fails. On top
+/// of the reading, this costs one query plus two writes for every parameter
+/// that has to be toggled.
+pub fn read_salinity(dev: &mut LinuxI2CDevice) -> Result<f64, EzoError> {
+    let previous = OutputState.run(dev)?;
+    let salinity_only = OutputStringStatus::new().with_salinity(true);
+
+    let reading = apply_output(dev, &previous, &salinity_only).and_then(|_| Reading.run(dev));
+    let restored = apply_output(dev, &salinity_only, &previous);
+
+    let reading = reading?;
+    restored?;
+    match reading {
+        ProbeReading::OneParameter(salinity) => Ok(salinity),
+        _ => Err(ErrorKind::ResponseParse)?,
+    }
+}
+
+/// Checks that `chunk` is a line of a calibration export that the chip can
+/// import: between 1 and `EXPORT_CHUNK_LEN` printable ASCII characters,
+/// without commas.
+///
+/// Lengths out of range fail with `ErrorKind::CommandParse`, caused by a
+/// `ValueOutOfRange`.
+pub fn validate_import_chunk(chunk: &str) -> Result<(), EzoError> {
+    ValueOutOfRange::check(chunk.len() as f64, 1.0, EXPORT_CHUNK_LEN as f64)?;
+    if chunk.bytes().all(|b| b.is_ascii_graphic() && b != b',') {
+        Ok(())
+    } else {
+        Err(ErrorKind::CommandParse)?
+    }
+}
+
+/// Builds the `Import` commands that restore a calibration export, after
+/// checking each line and that the lines add up to what `ExportInfo`
+/// reported.
+pub fn import_commands(chunks: &[String], info: &ExportedInfo) -> Result<Vec<Import>, EzoError> {
+    let total_bytes: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+    if chunks.len() != info.lines as usize || total_bytes != info.total_bytes as usize {
+        Err(ErrorKind::CommandParse)?;
+    }
+    chunks
+        .iter()
+        .map(|chunk| {
+            validate_import_chunk(chunk)?;
+            Ok(Import(chunk.clone()))
+        })
+        .collect()
+}
+
+/// Wakes the chip after `Sleep`, and confirms it answers again.
+///
+/// Any command wakes the chip, but its response is lost, so a harmless
+/// `K,?` query is sent twice: the first response is discarded, the second
+/// must succeed. This costs two full command cycles, about 600 ms.
+pub fn wake(dev: &mut LinuxI2CDevice) -> Result<(), EzoError> {
+    let _ = ProbeTypeState.run(dev);
+    ProbeTypeState.run(dev)?;
+    Ok(())
+}
+
+/// Wakes the chip after `Sleep`, over any `EzoTransport`. See `wake`.
+pub fn wake_on<T: transport::EzoTransport>(transport: &mut T) -> Result<(), EzoError> {
+    let _ = ProbeTypeState.run_on(transport);
+    ProbeTypeState.run_on(transport)?;
+    Ok(())
+}
+
+/// Wakes the chip at `addr` after `Sleep`, over an `embedded-hal` I2C bus.
+/// See `wake`.
+#[cfg(feature = "embedded-hal")]
+pub fn wake_hal<I: Write + Read>(dev: &mut I, addr: u8) -> Result<(), EzoError> {
+    wake_on(&mut HalTransport::new(dev, addr))
+}
+
+/// Restores factory settings, waits for the chip to restart, and applies the
+/// given probe type, output configuration and temperature compensation.
+///
+/// The chip is polled with `Status` until it answers. If it doesn't within
+/// `timeout`, this fails with `ErrorKind::I2CRead`, caused by a
+/// `DeviceTimeout`.
+pub fn factory_reset_and_reinit(
+    dev: &mut LinuxI2CDevice,
+    probe: ProbeType,
+    output: &OutputStringStatus,
+    temperature: Celsius,
+    timeout: Duration,
+) -> Result<(), EzoError> {
+    Factory.run(dev)?;
+
+    let start = Instant::now();
+    loop {
+        thread::sleep(Duration::from_millis(RESTART_POLL_INTERVAL));
+        if Status.run(dev).is_ok() {
+            break;
+        }
+        if start.elapsed() >= timeout {
+            return Err(DeviceTimeout { timeout }.context(ErrorKind::I2CRead).into());
+        }
+    }
+
+    match probe {
+        ProbeType::PointOne => ProbeTypePointOne.run(dev)?,
+        ProbeType::One => ProbeTypeOne.run(dev)?,
+        ProbeType::Ten => ProbeTypeTen.run(dev)?,
+    };
+    let current = OutputState.run(dev)?;
+    apply_output(dev, &current, output)?;
+    TemperatureCompensation(temperature).run(dev)?;
+    Ok(())
+}
+
+/// Largest difference between an applied and a read-back TDS factor or
+/// temperature that still counts as a match, as the chip rounds them.
+const SETTING_TOLERANCE: f64 = 0.01;
+
+/// Complete configuration of a sensor, to be kept in a file and applied at
+/// boot.
+///
+/// `name` identifies the sensor in the deployment, and isn't sent to the
+/// chip.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorConfig {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<String>,
+    pub probe: ProbeType,
+    pub output: OutputStringStatus,
+    pub tds_factor: f64,
+    pub temperature: Celsius,
+}
+
+impl SensorConfig {
+    /// Applies the probe type, output configuration, TDS factor and
+    /// temperature compensation, in that order, reading each one back.
+    ///
+    /// A setting that doesn't read back as applied fails with
+    /// `ErrorKind::DeviceErrorResponse`, caused by a `SettingMismatch`. An
+    /// output the probe can't produce is refused before anything is sent,
+    /// see `OutputParameter::validate_for_probe`.
+    pub fn apply(&self, dev: &mut LinuxI2CDevice) -> Result<(), EzoError> {
+        self.apply_with(|cmd| run_dyn(dev, cmd))
+    }
+
+    /// Like `apply`, over any `EzoTransport`.
+    pub fn apply_on<T: transport::EzoTransport>(&self, transport: &mut T) -> Result<(), EzoError> {
+        self.apply_with(|cmd| run_dyn_on(transport, cmd))
+    }
+
+    /// Like `apply`, over an `embedded-hal` I2C bus.
+    #[cfg(feature = "embedded-hal")]
+    pub fn apply_hal<I: Write + Read>(&self, dev: &mut I, addr: u8) -> Result<(), EzoError> {
+        self.apply_on(&mut HalTransport::new(dev, addr))
+    }
+
+    fn apply_with<F>(&self, mut run: F) -> Result<(), EzoError>
+    where
+        F: FnMut(&dyn DynCommand) -> Result<AnyResponse, EzoError>,
+    {
+        if self.output.specific_gravity.into() {
+            OutputParameter::SpecificGravity.validate_for_probe(self.probe)?;
+        }
+        run(probe_type_command(self.probe))?;
+        match run(&ProbeTypeState)? {
+            AnyResponse::ProbeType(probe) if probe == self.probe => {}
+            AnyResponse::ProbeType(probe) => {
+                return Err(mismatch("probe type", &self.probe, &probe));
+            }
+            _ => return Err(ErrorKind::ResponseParse)?,
+        }
+
+        let current = match run(&OutputState)? {
+            AnyResponse::Output(status) => status,
+            _ => return Err(ErrorKind::ResponseParse)?,
+        };
+        for cmd in current.diff_commands(&self.output) {
+            run(&cmd)?;
+        }
+        match run(&OutputState)? {
+            AnyResponse::Output(status) if status == self.output => {}
+            AnyResponse::Output(status) => {
+                return Err(mismatch("output", &self.output, &status));
+            }
+            _ => return Err(ErrorKind::ResponseParse)?,
+        }
+
+        run(&TdsConversionFactor(self.tds_factor))?;
+        match run(&TdsConversionFactorState)? {
+            AnyResponse::TdsFactor(factor)
+                if (factor.0 - self.tds_factor).abs() <= SETTING_TOLERANCE => {}
+            AnyResponse::TdsFactor(factor) => {
+                return Err(mismatch("TDS factor", &self.tds_factor, &factor));
+            }
+            _ => return Err(ErrorKind::ResponseParse)?,
+        }
+
+        run(&TemperatureCompensation(self.temperature))?;
+        match run(&CompensatedTemperatureValue)? {
+            AnyResponse::Compensation(value)
+                if (value.0 - self.temperature.0).abs() <= SETTING_TOLERANCE => {}
+            AnyResponse::Compensation(value) => {
+                return Err(mismatch(
+                    "temperature compensation",
+                    &self.temperature,
+                    &value,
+                ));
+            }
+            _ => return Err(ErrorKind::ResponseParse)?,
+        }
+        Ok(())
+    }
+}
+
+/// The command that sets the given probe type.
+pub(crate) fn probe_type_command(probe: ProbeType) -> &'static dyn DynCommand {
+    match probe {
+        ProbeType::PointOne => &ProbeTypePointOne,
+        ProbeType::One => &ProbeTypeOne,
+        ProbeType::Ten => &ProbeTypeTen,
+    }
+}
+
+/// Error for a `setting` that reads back as `actual` instead of `expected`.
+fn mismatch<E: fmt::Display, A: fmt::Display>(
+    setting: &'static str,
+    expected: &E,
+    actual: &A,
+) -> EzoError {
+    SettingMismatch {
+        setting,
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    }
+    .context(ErrorKind::DeviceErrorResponse)
+    .into()
+}
+
+/// Step of a `CalibrationWizard`, named after the calibration it runs next.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WizardState {
+    Dry,
+    Low,
+    High,
+    Verify,
+    Done,
+}
+
+/// Two-point calibration, run one step at a time: dry, low point, high
+/// point, and a final check of the calibration status.
+///
+/// After each calibration command, `CAL,?` is polled to confirm the point
+/// registered before moving on. The probe has to be moved into the next
+/// solution, and left to settle, between steps.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CalibrationWizard {
+    pub low: f64,
+    pub high: f64,
+    state: WizardState,
+}
+
+impl CalibrationWizard {
+    /// Wizard calibrating with `low` and `high` solutions, in µS/cm.
+    pub fn new(low: f64, high: f64) -> CalibrationWizard {
+        CalibrationWizard {
+            low,
+            high,
+            state: WizardState::Dry,
+        }
+    }
+
+    /// The step that runs next.
+    pub fn state(&self) -> WizardState {
+        self.state
+    }
+
+    /// Runs the current step, and returns the step that follows.
+    ///
+    /// A point that doesn't register fails with
+    /// `ErrorKind::DeviceErrorResponse`, caused by a `SettingMismatch`, and
+    /// the step is left to be retried.
+    pub fn step(&mut self, dev: &mut LinuxI2CDevice) -> Result<WizardState, EzoError> {
+        self.step_with(|cmd| run_dyn(dev, cmd))
+    }
+
+    /// Like `step`, over any `EzoTransport`.
+    pub fn step_on<T: transport::EzoTransport>(
+        &mut self,
+        transport: &mut T,
+    ) -> Result<WizardState, EzoError> {
+        self.step_with(|cmd| run_dyn_on(transport, cmd))
+    }
+
+    /// Like `step`, over an `embedded-hal` I2C bus.
+    #[cfg(feature = "embedded-hal")]
+    pub fn step_hal<I: Write + Read>(
+        &mut self,
+        dev: &mut I,
+        addr: u8,
+    ) -> Result<WizardState, EzoError> {
+        self.step_on(&mut HalTransport::new(dev, addr))
+    }
+
+    /// Iterates over the remaining steps, yielding the outcome of each one.
+    /// Ends after the last step, or after the first error.
+    pub fn steps<'a>(
+        &'a mut self,
+        dev: &'a mut LinuxI2CDevice,
+    ) -> impl Iterator<Item = Result<WizardState, EzoError>> + 'a {
+        let mut failed = false;
+        std::iter::from_fn(move || {
+            if failed || self.state == WizardState::Done {
+                return None;
+            }
+            let result = self.step(dev);
+            failed = result.is_err();
+            Some(result)
+        })
+    }
+
+    /// Like `steps`, over any `EzoTransport`.
+    pub fn steps_on<'a, T: transport::EzoTransport>(
+        &'a mut self,
+        transport: &'a mut T,
+    ) -> impl Iterator<Item = Result<WizardState, EzoError>> + 'a {
+        let mut failed = false;
+        std::iter::from_fn(move || {
+            if failed || self.state == WizardState::Done {
+                return None;
+            }
+            let result = self.step_on(transport);
+            failed = result.is_err();
+            Some(result)
+        })
+    }
+
+    /// Like `steps`, over an `embedded-hal` I2C bus.
+    #[cfg(feature = "embedded-hal")]
+    pub fn steps_hal<'a, I: Write + Read>(
+        &'a mut self,
+        dev: &'a mut I,
+        addr: u8,
+    ) -> impl Iterator<Item = Result<WizardState, EzoError>> + 'a {
+        let mut failed = false;
+        std::iter::from_fn(move || {
+            if failed || self.state == WizardState::Done {
+                return None;
+            }
+            let result = self.step_hal(dev, addr);
+            failed = result.is_err();
+            Some(result)
+        })
+    }
+
+    fn step_with<F>(&mut self, mut run: F) -> Result<WizardState, EzoError>
+    where
+        F: FnMut(&dyn DynCommand) -> Result<AnyResponse, EzoError>,
+    {
+        let (expected, next) = match self.state {
+            WizardState::Dry => {
+                run(&CalibrationDry)?;
+                (None, WizardState::Low)
+            }
+            WizardState::Low => {
+                run(&CalibrationLow(self.low))?;
+                (Some(CalibrationStatus::OnePoint), WizardState::High)
+            }
+            WizardState::High => {
+                run(&CalibrationHigh(self.high))?;
+                (Some(CalibrationStatus::TwoPoint), WizardState::Verify)
+            }
+            WizardState::Verify => (Some(CalibrationStatus::TwoPoint), WizardState::Done),
+            WizardState::Done => return Ok(WizardState::Done),
+        };
+        match run(&CalibrationState)? {
+            AnyResponse::Calibration(status) => match expected {
+                Some(expected) if status != expected => {
+                    return Err(mismatch("calibration", &expected, &status));
+                }
+                _ => {}
+            },
+            _ => return Err(ErrorKind::ResponseParse)?,
+        }
+        self.state = next;
+        Ok(next)
+    }
+}
+
+/// Bounds how long the kernel waits on a stalled transfer, so that a wedged
+/// bus makes `run` fail with `ErrorKind::I2CRead` instead of blocking forever.
+///
+/// This sets the timeout of the whole I2C adapter, for every device on the
+/// bus, rounded up to 10 ms. It needs Linux, and an adapter driver that
+/// honours `I2C_TIMEOUT`; failing to set it yields `ErrorKind::I2CWrite`,
+/// caused by the OS error.
+#[cfg(target_os = "linux")]
+pub fn set_bus_timeout(dev: &mut LinuxI2CDevice, timeout: Duration) -> Result<(), EzoError> {
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    let fd = dev.as_raw_fd();
+    let units = bus_timeout_units(timeout);
+    // SAFETY: `fd` is the open file of the I2C device, and `I2C_TIMEOUT`
+    // takes its argument by value.
+    let result = unsafe { libc::ioctl(fd, I2C_TIMEOUT as _, units) };
+    if result < 0 {
+        Err(io::Error::last_os_error().context(ErrorKind::I2CWrite))?;
+    }
+    Ok(())
+}
+
+/// `ioctl` request setting an I2C adapter's timeout, in units of 10 ms.
+#[cfg(target_os = "linux")]
+const I2C_TIMEOUT: libc::c_ulong = 0x0702;
+
+/// `timeout` in units of 10 ms, rounded up, for `I2C_TIMEOUT`.
+#[cfg(target_os = "linux")]
+fn bus_timeout_units(timeout: Duration) -> libc::c_ulong {
+    let units = timeout.as_millis().div_ceil(10);
+    units.min(libc::c_ulong::MAX as u128) as libc::c_ulong
+}
+
+/// Sends the commands that turn the `current` output configuration into
+/// `target`.
+fn apply_output(
+    dev: &mut LinuxI2CDevice,
+    current: &OutputStringStatus,
+    target: &OutputStringStatus,
+) -> Result<(), EzoError> {
+    for cmd in current.diff_commands(target) {
+        cmd.run(dev)?;
+    }
+    Ok(())
+}
+
+/// Like `run_sequence`, over an `embedded-hal` I2C bus.
+#[cfg(feature = "embedded-hal")]
+pub fn run_sequence_hal<I: Write + Read>(
+    dev: &mut I,
+    addr: u8,
+    cmds: &[&dyn DynCommand],
+) -> Result<(), SequenceError> {
+    trace_exchange!("talking to the chip at {:#04x}", addr);
+    run_sequence_on(&mut HalTransport::new(dev, addr), cmds)
+}
+
+/// Writes `cmd` to the chip, waits `delay` milliseconds, and returns its
+/// response string, read from at most `len` bytes.
+fn exchange<T: transport::EzoTransport>(
+    transport: &mut T,
+    cmd: &str,
+    delay: u64,
+    len: usize,
+) -> Result<String, EzoError> {
+    trace_exchange!("writing {:?}, then waiting {} ms", cmd, delay);
+    transport.write(cmd)?;
+    transport.delay(delay);
+    let mut data_buffer = [0u8; MAX_DATA];
+    let data = &mut data_buffer[..response_len(len)];
+    transport.read(data)?;
+    trace_exchange!("read {:?}", raw_response(data));
+    response_from_data(data)
+}
+
+/// Runs a command defined in `ezo_common`, which only runs on a
+/// `LinuxI2CDevice` by itself, over any `EzoTransport`. Its response is
+/// parsed with `parse`.
+pub(crate) fn run_common_on<T, C, R>(
+    transport: &mut T,
+    cmd: &C,
+    parse: fn(&str) -> Result<R, EzoError>,
+) -> Result<R, EzoError>
+where
+    T: transport::EzoTransport,
+    C: Command,
+{
+    let command = cmd.get_command_string();
+    exchange(transport, &command, cmd.get_delay(), MAX_DATA)
+        .and_then(|resp| parse(&resp))
+        .map_err(|error| CommandFailed::wrap(&command, error))
+}
+
+/// Runs a command defined in `ezo_common`, which only runs on a
+/// `LinuxI2CDevice` by itself, over an `embedded-hal` I2C bus. Its response
+/// is parsed with `parse`.
+#[cfg(feature = "embedded-hal")]
+pub(crate) fn run_common_hal<I, C, T>(
+    dev: &mut I,
+    addr: u8,
+    cmd: &C,
+    parse: fn(&str) -> Result<T, EzoError>,
+) -> Result<T, EzoError>
+where
+    I: Write + Read,
+    C: Command,
+{
+    trace_exchange!("talking to the chip at {:#04x}", addr);
+    run_common_on(&mut HalTransport::new(dev, addr), cmd, parse)
+}
+
+/// Writes `cmd` to the chip at `addr`, retrying once after 100ms, without
+/// reading a response back.
+#[cfg(feature = "embedded-hal")]
+pub(crate) fn hal_write<I: Write>(dev: &mut I, addr: u8, cmd: &str) -> Result<(), EzoError> {
+    bus::write_command(dev, addr, &mut ThreadDelay, cmd)
+}
+
+/// Runs the command over an `embedded-hal-async` I2C bus, talking to the
+/// chip at address `addr`.
+///
+/// The wait between writing the command and reading its response (e.g.,
+/// 800ms for calibration) is honored by awaiting the injected `delay` timer,
+/// so no thread is blocked while the chip processes the command.
+#[cfg(feature = "async")]
+pub async fn run_async<C, I, D>(
+    cmd: &C,
+    dev: &mut I,
+    addr: u8,
+    delay: &mut D,
+) -> Result<C::Response, EzoError>
+where
+    C: EcCommand,
+    I: I2c,
+    D: DelayNs,
+{
+    let command = cmd.get_command_string();
+    let resp = bus::exchange_async(
+        dev,
+        addr,
+        delay,
+        &command,
+        cmd.get_delay(),
+        cmd.max_response_len(),
+    )
+    .await;
+    let result = match accept_no_data(resp, cmd.accepts_no_data()) {
+        Ok(resp) => cmd.parse_response(&resp),
+        Err(error) => Err(error),
+    };
+    result.map_err(|error| CommandFailed::wrap(&command, error))
+}
+
+/// Turns the chip's `255` "no data" response code into an empty response
+/// when the command `accepts` it, as setters do.
+fn accept_no_data(result: Result<String, EzoError>, accepts: bool) -> Result<String, EzoError> {
+    match result {
+        Err(ref error) if accepts && error.kind() == ErrorKind::NoDataExpectedResponse => {
+            Ok(String::new())
+        }
+        result => result,
+    }
+}
+
+/// Runs a setter on a `LinuxI2CDevice`, accepting the chip's `255` "no data"
+/// response code as `ezo_common`'s generated `Command::run` doesn't.
+fn run_ack<C>(cmd: &C, dev: &mut LinuxI2CDevice) -> Result<ResponseStatus, EzoError>
+where
+    C: EcCommand<Response = ResponseStatus>,
+{
+    let command = cmd.get_command_string();
+    let resp = exchange(dev, &command, cmd.get_delay(), cmd.max_response_len());
+    accept_no_data(resp, cmd.accepts_no_data())
+        .and_then(|resp| cmd.parse_response(&resp))
+        .map_err(|error| CommandFailed::wrap(&command, error))
+}
+
+/// Defines a command and implements `EcCommand` for it with the same
+/// response parsing. Queries are defined through `define_command!`; setters
+/// get their own `Command::run`, through `run_ack`, so that it accepts the
+/// `255` "no data" response code.
+macro_rules! define_ec_command {
+    (doc: $doc:tt, $name:ident, $command_string:block, $delay:expr, Ack
+     $(, cooldown: $cooldown:expr)?) => {
+        #[doc = $doc]
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name;
+
+        impl Command for $name {
+            type Error = EzoError;
+            type Response = ResponseStatus;
+
+            fn get_command_string(&self) -> String {
+                $command_string
+            }
+
+            fn get_delay(&self) -> u64 {
+                $delay
+            }
+
+            fn run(&self, dev: &mut LinuxI2CDevice) -> Result<ResponseStatus, EzoError> {
+                run_ack(self, dev)
+            }
+        }
+
+        impl EcCommand for $name {
+            fn parse_response(&self, _response: &str) -> Result<ResponseStatus, EzoError> {
+                Ok(ResponseStatus::Ack)
+            }
+
+            fn max_response_len(&self) -> usize {
+                ACK_RESPONSE_LEN
+            }
+
+            fn accepts_no_data(&self) -> bool {
+                true
+            }
+
+            $(
+                fn cooldown(&self) -> Duration {
+                    Duration::from_millis($cooldown)
+                }
+            )?
+        }
+    };
+    (doc: $doc:tt, $cmd:ident : $name:ident($data:ty), $command_string:block, $delay:expr, Ack
+     $(, cooldown: $cooldown:expr)?) => {
+        #[doc = $doc]
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name(pub $data);
+
+        impl Command for $name {
+            type Error = EzoError;
+            type Response = ResponseStatus;
+
+            fn get_command_string(&self) -> String {
+                let $cmd = &self.0;
+                $command_string
+            }
+
+            fn get_delay(&self) -> u64 {
+                $delay
+            }
+
+            fn run(&self, dev: &mut LinuxI2CDevice) -> Result<ResponseStatus, EzoError> {
+                run_ack(self, dev)
+            }
+        }
+
+        impl EcCommand for $name {
+            fn parse_response(&self, _response: &str) -> Result<ResponseStatus, EzoError> {
+                Ok(ResponseStatus::Ack)
+            }
+
+            fn max_response_len(&self) -> usize {
+                ACK_RESPONSE_LEN
+            }
+
+            fn accepts_no_data(&self) -> bool {
+                true
+            }
+
+            $(
+                fn cooldown(&self) -> Duration {
+                    Duration::from_millis($cooldown)
+                }
+            )?
+        }
+    };
+    (doc: $doc:tt, $name:ident, $command_string:block, $delay:expr,
+     $resp:ident : $response:ty, $parse:block) => {
+        define_command! { doc: $doc, $name, $command_string, $delay, $resp: $response, $parse }
+
+        impl EcCommand for $name {
+            // The parse block is shared with `define_command!`, where `resp` is a `String`.
+            #[allow(clippy::needless_borrow)]
+            fn parse_response(&self, $resp: &str) -> Result<$response, EzoError> {
+                $parse
+            }
+        }
+    };
+    (doc: $doc:tt, $cmd:ident : $name:ident($data:ty), $command_string:block, $delay:expr,
+     $resp:ident : $response:ty, $parse:block) => {
+        define_command! {
+            doc: $doc, $cmd: $name($data), $command_string, $delay, $resp: $response, $parse
+        }
+
+        impl EcCommand for $name {
+            // The parse block is shared with `define_command!`, where `resp` is a `String`.
+            #[allow(clippy::needless_borrow)]
+            fn parse_response(&self, $resp: &str) -> Result<$response, EzoError> {
+                $parse
+            }
+        }
+    };
+}
+
+/// Long forms accepted for the first word of a command, with the word the
+/// chip expects.
+///
+/// | Alias                      | Command |
+/// |----------------------------|---------|
+/// | `READ`, `READING`          | `R`     |
+/// | `CALIBRATE`, `CALIBRATION` | `CAL`   |
+/// | `PROBE`                    | `K`     |
+/// | `OUTPUT`                   | `O`     |
+/// | `TEMP`, `TEMPERATURE`      | `T`     |
+const COMMAND_ALIASES: &[(&str, &str)] = &[
+    ("READ", "R"),
+    ("READING", "R"),
+    ("CALIBRATE", "CAL"),
+    ("CALIBRATION", "CAL"),
+    ("PROBE", "K"),
+    ("OUTPUT", "O"),
+    ("TEMP", "T"),
+    ("TEMPERATURE", "T"),
+];
+
+/// Uppercases a command typed by a user, and replaces a long form of its
+/// first word with the chip's own, e.g. `"read"` becomes `"R"` and
+/// `"calibrate,dry"` becomes `"CAL,DRY"`. See `COMMAND_ALIASES`.
+fn canonical_command(s: &str) -> String {
+    let supper = s.trim().to_uppercase();
+    let (head, rest) = match supper.find(',') {
+        Some(i) => supper.split_at(i),
+        None => (supper.as_str(), ""),
+    };
+    match COMMAND_ALIASES.iter().find(|&&(alias, _)| alias == head) {
+        Some(&(_, canonical)) => format!("{}{}", canonical, rest),
+        None => supper,
+    }
+}
+
+define_ec_command! {
+    doc: "`CAL,?` command. Returns a `CalibrationStatus` response. Current calibration status.",
+    CalibrationState, { "CAL,?".to_string() }, 300,
+    resp: CalibrationStatus, { CalibrationStatus::parse(&resp) }
+}
+
+impl FromStr for CalibrationState {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        match supper.as_ref() {
+            "CAL,?" => Ok(CalibrationState),
+            _ => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`CAL,DRY` command. Performs calibration.",
+    CalibrationDry, { "CAL,DRY".to_string() }, 800, Ack, cooldown: CALIBRATION_COOLDOWN
+}
+
+impl FromStr for CalibrationDry {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        match supper.as_ref() {
+            "CAL,DRY" => Ok(CalibrationDry),
+            _ => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+/// Range of sensible calibration solutions, in µS/cm, for a probe type.
+///
+/// Spans an order of magnitude around the datasheet's recommended
+/// calibration standards, capped by the probe's measurement range.
+fn calibration_range(probe: ProbeType) -> (f64, f64) {
+    match probe {
+        ProbeType::PointOne => (8.4, 14_130.0),
+        ProbeType::One => (1_288.0, 200_000.0),
+        ProbeType::Ten => (1_288.0, 1_000_000.0),
+    }
+}
+
+/// Checks that a calibration solution value suits the given probe type.
+fn validate_calibration_value(value: f64, probe: ProbeType) -> Result<(), EzoError> {
+    let (min, max) = calibration_range(probe);
+    ValueOutOfRange::check(value, min, max)
+}
+
+define_ec_command! {
+    doc: "`CAL,n` command, where `n` is a `f64` number. Performs calibration.",
+    cmd: CalibrationOnePoint(f64), { format!("CAL,{:.*}", 2, cmd) }, 800, Ack,
+    cooldown: CALIBRATION_COOLDOWN
+}
+
+impl CalibrationOnePoint {
+    /// Checks that the calibration value suits the probe type, catching
+    /// solutions that are far outside what the probe can measure.
+    pub fn validate_for_probe(&self, probe: ProbeType) -> Result<(), EzoError> {
+        validate_calibration_value(self.0, probe)
+    }
+}
+
+impl FromStr for CalibrationOnePoint {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        let rest = match supper.strip_prefix("CAL,") {
+            Some(rest) => rest,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        let mut split = rest.split(',');
+        let value = match split.next() {
+            Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        match split.next() {
+            None => Ok(CalibrationOnePoint(value)),
+            Some(_) => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`CAL,LOW,t` command, where `t` is of type `f64`. Performs calibration.",
+    cmd: CalibrationLow(f64), { format!("CAL,LOW,{:.*}", 2, cmd) }, 800, Ack,
+    cooldown: CALIBRATION_COOLDOWN
+}
+
+impl CalibrationLow {
+    /// Checks that the calibration value suits the probe type, catching
+    /// solutions that are far outside what the probe can measure.
+    pub fn validate_for_probe(&self, probe: ProbeType) -> Result<(), EzoError> {
+        validate_calibration_value(self.0, probe)
+    }
+}
+
+impl FromStr for CalibrationLow {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        let rest = match supper.strip_prefix("CAL,LOW,") {
+            Some(rest) => rest,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        let mut split = rest.split(',');
+        let value = match split.next() {
+            Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        match split.next() {
+            None => Ok(CalibrationLow(value)),
+            Some(_) => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`CAL,HIGH,t` command, where `t` is of type `f64`. Performs calibration.",
+    cmd: CalibrationHigh(f64), { format!("CAL,HIGH,{:.*}", 2, cmd) }, 800, Ack,
+    cooldown: CALIBRATION_COOLDOWN
+}
+
+impl CalibrationHigh {
+    /// Checks that the calibration value suits the probe type, catching
+    /// solutions that are far outside what the probe can measure.
+    pub fn validate_for_probe(&self, probe: ProbeType) -> Result<(), EzoError> {
+        validate_calibration_value(self.0, probe)
+    }
+}
+
+impl FromStr for CalibrationHigh {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        let rest = match supper.strip_prefix("CAL,HIGH,") {
+            Some(rest) => rest,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        let mut split = rest.split(',');
+        let value = match split.next() {
+            Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        match split.next() {
+            None => Ok(CalibrationHigh(value)),
+            Some(_) => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`K,0.1` command. Set probe type to `0.1`.",
+    ProbeTypePointOne, { "K,0.1".to_string() }, 600, Ack
+}
+
+impl FromStr for ProbeTypePointOne {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        match supper.as_ref() {
+            "K,0.1" => Ok(ProbeTypePointOne),
+            _ => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`K,1.0` command. Set probe type to `1.0`.",
+    ProbeTypeOne, { "K,1.0".to_string() }, 600, Ack
+}
+
+impl FromStr for ProbeTypeOne {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        match supper.as_ref() {
+            "K,1.0" => Ok(ProbeTypeOne),
+            _ => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`K,10.0` command. Set probe type to `10.0`.",
+    ProbeTypeTen, { "K,10.0".to_string() }, 600, Ack
+}
+
+impl FromStr for ProbeTypeTen {
+    type Err = EzoError;
 
-pub use ezo_common::command::{
-    Baud, CalibrationClear, DeviceAddress, DeviceInformation, Export, ExportInfo, Factory, Find,
-    Import, LedOff, LedOn, LedState, ProtocolLockDisable, ProtocolLockEnable, ProtocolLockState,
-    Sleep, Status,
-};
-/// I2C command for the EZO chip.
-pub use ezo_common::Command;
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        match supper.as_ref() {
+            "K,10.0" => Ok(ProbeTypeTen),
+            _ => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
 
-define_command! {
-    doc: "`CAL,?` command. Returns a `CalibrationStatus` response. Current calibration status.",
-    CalibrationState, { "CAL,?".to_string() }, 300,
-    resp: CalibrationStatus, { CalibrationStatus::parse(&resp) }
+define_ec_command! {
+    doc: "`K,?` command. Returns a `ProbeType` response. Get current probe type.",
+    ProbeTypeState, { "K,?".to_string() }, 300,
+    resp: ProbeType, { ProbeType::parse(&resp) }
 }
 
-impl FromStr for CalibrationState {
+impl FromStr for ProbeTypeState {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "CAL,?" => Ok(CalibrationState),
-            _ => return Err(ErrorKind::CommandParse)?,
+            "K,?" => Ok(ProbeTypeState),
+            _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`CAL,DRY` command. Performs calibration.",
-    CalibrationDry, { "CAL,DRY".to_string() }, 800, Ack
+define_ec_command! {
+    doc: "`R` command. Returns a `ProbeReading` response. Returns a single reading.",
+    Reading, { "R".to_string() }, 600,
+    resp: ProbeReading, { ProbeReading::parse(&resp) }
 }
 
-impl FromStr for CalibrationDry {
+impl FromStr for Reading {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "CAL,DRY" => Ok(CalibrationDry),
-            _ => return Err(ErrorKind::CommandParse)?,
+            "R" => Ok(Reading),
+            _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`CAL,n` command, where `n` is a `f64` number. Performs calibration.",
-    cmd: CalibrationOnePoint(f64), { format!("CAL,{:.*}", 2, cmd) }, 800, Ack
+/// Parameter that can be shown or hidden in the output string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OutputParameter {
+    Conductivity,
+    Tds,
+    Salinity,
+    SpecificGravity,
 }
 
-impl FromStr for CalibrationOnePoint {
+impl OutputParameter {
+    /// Name of the parameter in `O,...` commands.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            OutputParameter::Conductivity => "EC",
+            OutputParameter::Tds => "TDS",
+            OutputParameter::Salinity => "S",
+            OutputParameter::SpecificGravity => "SG",
+        }
+    }
+
+    /// Checks that the probe type can produce the parameter: specific gravity
+    /// needs a K 1.0 or K 10 probe.
+    ///
+    /// Otherwise, fails with `ErrorKind::CommandParse`, caused by an
+    /// `UnsupportedOutput`.
+    pub fn validate_for_probe(&self, probe: ProbeType) -> Result<(), EzoError> {
+        match *self {
+            OutputParameter::SpecificGravity if !probe.supports_specific_gravity() => {
+                Err(UnsupportedOutput {
+                    parameter: *self,
+                    probe,
+                }
+                .context(ErrorKind::CommandParse)
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for OutputParameter {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
         let supper = s.to_uppercase();
-        if supper.starts_with("CAL,") {
-            let rest = supper.get(4..).unwrap();
-            let mut split = rest.split(',');
-            let value = match split.next() {
-                Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
-                _ => return Err(ErrorKind::CommandParse)?,
-            };
-            match split.next() {
-                None => return Ok(CalibrationOnePoint(value)),
-                _ => return Err(ErrorKind::CommandParse)?,
-            }
+        match supper.as_ref() {
+            "EC" => Ok(OutputParameter::Conductivity),
+            "TDS" => Ok(OutputParameter::Tds),
+            "S" => Ok(OutputParameter::Salinity),
+            "SG" => Ok(OutputParameter::SpecificGravity),
+            _ => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+impl From<&ProbeMetric> for OutputParameter {
+    fn from(metric: &ProbeMetric) -> OutputParameter {
+        match *metric {
+            ProbeMetric::ElectricConductivity(_) => OutputParameter::Conductivity,
+            ProbeMetric::TotalDissolvedSolids(_) => OutputParameter::Tds,
+            ProbeMetric::Salinity(_) => OutputParameter::Salinity,
+            ProbeMetric::SpecificGravity(_) => OutputParameter::SpecificGravity,
+        }
+    }
+}
+
+/// `O,<parameter>,<0|1>` command. Enable or disable a parameter in the output string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputConfigure {
+    pub parameter: OutputParameter,
+    pub enable: bool,
+}
+
+impl OutputConfigure {
+    /// Checks that a parameter being enabled can be produced by the probe
+    /// type. See `OutputParameter::validate_for_probe`.
+    pub fn validate_for_probe(&self, probe: ProbeType) -> Result<(), EzoError> {
+        if self.enable {
+            self.parameter.validate_for_probe(probe)
         } else {
-            return Err(ErrorKind::CommandParse)?;
+            Ok(())
         }
     }
 }
 
-define_command! {
-    doc: "`CAL,LOW,t` command, where `t` is of type `f64`. Performs calibration.",
-    cmd: CalibrationLow(f64), { format!("CAL,LOW,{:.*}", 2, cmd) }, 800, Ack
+impl Command for OutputConfigure {
+    type Error = EzoError;
+    type Response = ResponseStatus;
+
+    fn get_command_string(&self) -> String {
+        format!("O,{},{}", self.parameter.as_str(), self.enable as u8)
+    }
+
+    fn get_delay(&self) -> u64 {
+        300
+    }
+
+    fn run(&self, dev: &mut LinuxI2CDevice) -> Result<ResponseStatus, EzoError> {
+        run_ack(self, dev)
+    }
 }
 
-impl FromStr for CalibrationLow {
+impl EcCommand for OutputConfigure {
+    fn parse_response(&self, _response: &str) -> Result<ResponseStatus, EzoError> {
+        Ok(ResponseStatus::Ack)
+    }
+
+    fn max_response_len(&self) -> usize {
+        ACK_RESPONSE_LEN
+    }
+
+    fn accepts_no_data(&self) -> bool {
+        true
+    }
+}
+
+impl FromStr for OutputConfigure {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
-        if supper.starts_with("CAL,LOW,") {
-            let rest = supper.get(8..).unwrap();
+        let supper = canonical_command(s);
+        if supper.starts_with("O,") {
+            let rest = supper.get(2..).unwrap();
             let mut split = rest.split(',');
-            let value = match split.next() {
-                Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
+            let parameter = match split.next() {
+                Some(p) => p.parse::<OutputParameter>()?,
                 _ => return Err(ErrorKind::CommandParse)?,
             };
-            match split.next() {
-                None => return Ok(CalibrationLow(value)),
+            let enable = match split.next() {
+                Some("0") => false,
+                Some("1") => true,
                 _ => return Err(ErrorKind::CommandParse)?,
+            };
+            match split.next() {
+                None => Ok(OutputConfigure { parameter, enable }),
+                _ => Err(ErrorKind::CommandParse)?,
             }
         } else {
-            return Err(ErrorKind::CommandParse)?;
+            Err(ErrorKind::CommandParse)?
         }
     }
 }
 
-define_command! {
-    doc: "`CAL,HIGH,t` command, where `t` is of type `f64`. Performs calibration.",
-    cmd: CalibrationHigh(f64), { format!("CAL,HIGH,{:.*}", 2, cmd) }, 800, Ack
+/// Longest command string a `RawCommand` accepts.
+pub const MAX_COMMAND_LEN: usize = 40;
+
+/// Command the crate doesn't model, sent as given. Built with
+/// `RawCommand::new`, which checks the command string.
+///
+/// Its response is the trimmed response string. A command that doesn't
+/// expect a response is treated as a setter: an empty string is returned,
+/// and the chip's "no data" response code counts as success.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCommand {
+    command: String,
+    delay: Duration,
+    expects_response: bool,
 }
 
-impl FromStr for CalibrationHigh {
+impl RawCommand {
+    /// Raw command sending `command`, waiting 300 ms for a response.
+    ///
+    /// Fails with `ErrorKind::CommandParse` unless `command` is printable
+    /// ASCII. When it is empty or longer than `MAX_COMMAND_LEN`, the error
+    /// is caused by a `ValueOutOfRange`.
+    pub fn new(command: &str) -> Result<RawCommand, EzoError> {
+        ValueOutOfRange::check(command.len() as f64, 1.0, MAX_COMMAND_LEN as f64)?;
+        if !command.bytes().all(|b| b.is_ascii_graphic() || b == b' ') {
+            Err(ErrorKind::CommandParse)?;
+        }
+        Ok(RawCommand {
+            command: command.to_string(),
+            delay: Duration::from_millis(300),
+            expects_response: true,
+        })
+    }
+
+    /// Sets how long to wait for the response.
+    pub fn with_response_delay(mut self, delay: Duration) -> RawCommand {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets whether the chip answers with data, or only acknowledges the
+    /// command.
+    pub fn with_expected_response(mut self, expects_response: bool) -> RawCommand {
+        self.expects_response = expects_response;
+        self
+    }
+
+    /// Whether the chip answers with data.
+    pub fn expects_response(&self) -> bool {
+        self.expects_response
+    }
+}
+
+impl Command for RawCommand {
+    type Error = EzoError;
+    type Response = String;
+
+    fn get_command_string(&self) -> String {
+        self.command.clone()
+    }
+
+    fn get_delay(&self) -> u64 {
+        self.delay.as_millis() as u64
+    }
+
+    fn run(&self, dev: &mut LinuxI2CDevice) -> Result<String, EzoError> {
+        self.run_on(dev)
+    }
+}
+
+impl EcCommand for RawCommand {
+    fn parse_response(&self, response: &str) -> Result<String, EzoError> {
+        Ok(response.trim().to_string())
+    }
+
+    fn max_response_len(&self) -> usize {
+        if self.expects_response {
+            MAX_DATA
+        } else {
+            ACK_RESPONSE_LEN
+        }
+    }
+
+    fn accepts_no_data(&self) -> bool {
+        !self.expects_response
+    }
+}
+
+impl OutputStringStatus {
+    /// Commands that turn this output configuration into `target`, one per
+    /// parameter that differs, in the order the chip reports them.
+    ///
+    /// Parameters that already match are left alone, sparing the chip's
+    /// EEPROM a redundant write.
+    pub fn diff_commands(&self, target: &OutputStringStatus) -> Vec<OutputConfigure> {
+        [
+            (
+                OutputParameter::Conductivity,
+                self.electric_conductivity,
+                target.electric_conductivity,
+            ),
+            (
+                OutputParameter::Tds,
+                self.total_dissolved_solids,
+                target.total_dissolved_solids,
+            ),
+            (OutputParameter::Salinity, self.salinity, target.salinity),
+            (
+                OutputParameter::SpecificGravity,
+                self.specific_gravity,
+                target.specific_gravity,
+            ),
+        ]
+        .iter()
+        .filter(|&&(_, current, wanted)| current != wanted)
+        .map(|&(parameter, _, wanted)| OutputConfigure {
+            parameter,
+            enable: wanted.into(),
+        })
+        .collect()
+    }
+}
+
+impl ProbeReading {
+    /// Values of the reading keyed by the parameters enabled in `status`.
+    ///
+    /// Fails with `ErrorKind::ResponseParse` when the number of values does not
+    /// match the number of enabled parameters.
+    pub fn to_map(
+        &self,
+        status: &OutputStringStatus,
+    ) -> Result<HashMap<OutputParameter, f64>, EzoError> {
+        let labeled = LabeledReading::new(self, status)?;
+        Ok(labeled
+            .metrics()
+            .iter()
+            .map(|metric| (OutputParameter::from(metric), metric.value()))
+            .collect())
+    }
+}
+
+define_ec_command! {
+    doc: "`O,EC,0` command. Disable conductivity in the output string.",
+    OutputDisableConductivity, {
+        OutputConfigure {
+            parameter: OutputParameter::Conductivity,
+            enable: false,
+        }
+        .get_command_string()
+    }, 300, Ack
+}
+
+impl FromStr for OutputDisableConductivity {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
-        if supper.starts_with("CAL,HIGH,") {
-            let rest = supper.get(9..).unwrap();
-            let mut split = rest.split(',');
-            let value = match split.next() {
-                Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
-                _ => return Err(ErrorKind::CommandParse)?,
-            };
-            match split.next() {
-                None => return Ok(CalibrationHigh(value)),
-                _ => return Err(ErrorKind::CommandParse)?,
-            }
-        } else {
-            return Err(ErrorKind::CommandParse)?;
+        let supper = canonical_command(s);
+        match supper.as_ref() {
+            "O,EC,0" => Ok(OutputDisableConductivity),
+            _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`K,0.1` command. Set probe type to `0.1`.",
-    ProbeTypePointOne, { "K,0.1".to_string() }, 600, Ack
+define_ec_command! {
+    doc: "`O,EC,1` command. Enable conductivity in the output string.",
+    OutputEnableConductivity, {
+        OutputConfigure {
+            parameter: OutputParameter::Conductivity,
+            enable: true,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for ProbeTypePointOne {
+impl FromStr for OutputEnableConductivity {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "K,0.1" => Ok(ProbeTypePointOne),
+            "O,EC,1" => Ok(OutputEnableConductivity),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`K,1.0` command. Set probe type to `1.0`.",
-    ProbeTypeOne, { "K,1.0".to_string() }, 600, Ack
+define_ec_command! {
+    doc: "`O,TDS,0` command. Disable total dissolved solids in the output string.",
+    OutputDisableTds, {
+        OutputConfigure {
+            parameter: OutputParameter::Tds,
+            enable: false,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for ProbeTypeOne {
+impl FromStr for OutputDisableTds {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "K,1.0" => Ok(ProbeTypeOne),
+            "O,TDS,0" => Ok(OutputDisableTds),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`K,10.0` command. Set probe type to `10.0`.",
-    ProbeTypeTen, { "K,10.0".to_string() }, 600, Ack
+define_ec_command! {
+    doc: "`O,TDS,1` command. Enable total dissolved solids in the output string.",
+    OutputEnableTds, {
+        OutputConfigure {
+            parameter: OutputParameter::Tds,
+            enable: true,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for ProbeTypeTen {
+impl FromStr for OutputEnableTds {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "K,10.0" => Ok(ProbeTypeTen),
+            "O,TDS,1" => Ok(OutputEnableTds),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`K,?` command. Returns a `ProbeType` response. Get current probe type.",
-    ProbeTypeState, { "K,?".to_string() }, 300,
-    resp: ProbeType, { ProbeType::parse(&resp) }
+define_ec_command! {
+    doc: "`O,S,0` command. Disable salinity in the output string.",
+    OutputDisableSalinity, {
+        OutputConfigure {
+            parameter: OutputParameter::Salinity,
+            enable: false,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for ProbeTypeState {
+impl FromStr for OutputDisableSalinity {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "K,?" => Ok(ProbeTypeState),
+            "O,S,0" => Ok(OutputDisableSalinity),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`R` command. Returns a `ProbeReading` response. Returns a single reading.",
-    Reading, { "R".to_string() }, 600,
-    resp: ProbeReading, { ProbeReading::parse(&resp) }
+define_ec_command! {
+    doc: "`O,S,1` command. Enable salinity in the output string.",
+    OutputEnableSalinity, {
+        OutputConfigure {
+            parameter: OutputParameter::Salinity,
+            enable: true,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for Reading {
+impl FromStr for OutputEnableSalinity {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "R" => Ok(Reading),
+            "O,S,1" => Ok(OutputEnableSalinity),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,EC,0` command. Disable conductivity in the output string.",
-    OutputDisableConductivity, { "O,EC,0".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`O,SG,0` command. Disable specific gravity in the output string.",
+    OutputDisableSpecificGravity, {
+        OutputConfigure {
+            parameter: OutputParameter::SpecificGravity,
+            enable: false,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for OutputDisableConductivity {
+impl FromStr for OutputDisableSpecificGravity {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,EC,0" => Ok(OutputDisableConductivity),
+            "O,SG,0" => Ok(OutputDisableSpecificGravity),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,EC,1` command. Enable conductivity in the output string.",
-    OutputEnableConductivity, { "O,EC,1".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`O,SG,1` command. Enable specific gravity in the output string.",
+    OutputEnableSpecificGravity, {
+        OutputConfigure {
+            parameter: OutputParameter::SpecificGravity,
+            enable: true,
+        }
+        .get_command_string()
+    }, 300, Ack
 }
 
-impl FromStr for OutputEnableConductivity {
+impl OutputEnableSpecificGravity {
+    /// Checks that the probe type can produce specific gravity, catching a
+    /// K 0.1 probe, for which the chip only reports `0.000`.
+    pub fn validate_for_probe(&self, probe: ProbeType) -> Result<(), EzoError> {
+        OutputParameter::SpecificGravity.validate_for_probe(probe)
+    }
+}
+
+impl FromStr for OutputEnableSpecificGravity {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,EC,1" => Ok(OutputEnableConductivity),
+            "O,SG,1" => Ok(OutputEnableSpecificGravity),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,TDS,0` command. Disable total dissolved solids in the output string.",
-    OutputDisableTds, { "O,TDS,0".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`O,?` command. Returns an `OutputStringStatus` response. Displays the enabled parameters for the output string.",
+    OutputState, { "O,?".to_string() }, 300,
+    resp: OutputStringStatus, { OutputStringStatus::parse(&resp) }
 }
 
-impl FromStr for OutputDisableTds {
+impl FromStr for OutputState {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,TDS,0" => Ok(OutputDisableTds),
+            "O,?" => Ok(OutputState),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,TDS,1` command. Enable total dissolved solids in the output string.",
-    OutputEnableTds, { "O,TDS,1".to_string() }, 300, Ack
+/// Parses an uppercased temperature, in Celsius unless suffixed with `F`.
+/// `C`, `°C` and `°F` suffixes are also accepted.
+///
+/// Temperatures below absolute zero fail with `ErrorKind::CommandParse`,
+/// caused by a `ValueOutOfRange`.
+fn parse_temperature(s: &str) -> Result<Celsius, EzoError> {
+    let (number, scale) = if let Some(n) = s.strip_suffix('F') {
+        (n, TemperatureScale::Fahrenheit)
+    } else if let Some(n) = s.strip_suffix('C') {
+        (n, TemperatureScale::Celsius)
+    } else {
+        (s, TemperatureScale::Celsius)
+    };
+    let number = number.strip_suffix('°').unwrap_or(number);
+    let value = number.parse::<f64>().context(ErrorKind::CommandParse)?;
+    let celsius = CompensationValue::from_scale(value, scale).0;
+    ValueOutOfRange::check(celsius, ABSOLUTE_ZERO, f64::INFINITY)?;
+    Ok(Celsius(celsius))
 }
 
-impl FromStr for OutputEnableTds {
+define_ec_command! {
+    doc: "`T,t` command, where `t` is a temperature in `Celsius`. Temperature compensation.",
+    cmd: TemperatureCompensation(Celsius), { format!("T,{:.*}", 3, cmd.0) }, 300, Ack
+}
+
+impl FromStr for TemperatureCompensation {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
+        let rest = match supper.strip_prefix("T,") {
+            Some(rest) => rest,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        let mut split = rest.split(',');
+        let value = match split.next() {
+            Some(n) => parse_temperature(n)?,
+            None => Err(ErrorKind::CommandParse)?,
+        };
+        match split.next() {
+            None => Ok(TemperatureCompensation(value)),
+            Some(_) => Err(ErrorKind::CommandParse)?,
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`T,?` command. Returns a `CompensationValue` response. Compensated temperature value.",
+    CompensatedTemperatureValue, { "T,?".to_string() }, 300,
+    resp: CompensationValue, { CompensationValue::parse(&resp) }
+}
+
+impl FromStr for CompensatedTemperatureValue {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,TDS,1" => Ok(OutputEnableTds),
+            "T,?" => Ok(CompensatedTemperatureValue),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,S,0` command. Disable salinity in the output string.",
-    OutputDisableSalinity, { "O,S,0".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`RT,t` command, where `t` is a temperature in `Celsius`. Returns a `ProbeReading` response. Sets temperature compensation and returns a single reading.",
+    cmd: ReadingWithTemperature(Celsius), { format!("RT,{:.*}", 3, cmd.0) }, 600,
+    resp: ProbeReading, { ProbeReading::parse(&resp) }
+}
+
+impl FromStr for ReadingWithTemperature {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        let supper = canonical_command(s);
+        if supper.starts_with("RT,") {
+            let rest = supper.get(3..).unwrap();
+            let mut split = rest.split(',');
+            let value = match split.next() {
+                Some(n) => parse_temperature(n)?,
+                _ => return Err(ErrorKind::CommandParse)?,
+            };
+            match split.next() {
+                None => Ok(ReadingWithTemperature(value)),
+                _ => Err(ErrorKind::CommandParse)?,
+            }
+        } else {
+            Err(ErrorKind::CommandParse)?
+        }
+    }
+}
+
+define_ec_command! {
+    doc: "`TDS,n` command, where `n` is of type `f64`. Sets the conductivity to total dissolved solids conversion factor.",
+    cmd: TdsConversionFactor(f64), { format!("TDS,{:.*}", 2, cmd) }, 300, Ack
 }
 
-impl FromStr for OutputDisableSalinity {
+impl FromStr for TdsConversionFactor {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
-        match supper.as_ref() {
-            "O,S,0" => Ok(OutputDisableSalinity),
-            _ => Err(ErrorKind::CommandParse)?,
+        let supper = canonical_command(s);
+        if supper.starts_with("TDS,") {
+            let rest = supper.get(4..).unwrap();
+            let mut split = rest.split(',');
+            let value = match split.next() {
+                Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
+                _ => return Err(ErrorKind::CommandParse)?,
+            };
+            ValueOutOfRange::check(value, 0.01, 1.0)?;
+            match split.next() {
+                None => Ok(TdsConversionFactor(value)),
+                _ => Err(ErrorKind::CommandParse)?,
+            }
+        } else {
+            Err(ErrorKind::CommandParse)?
         }
     }
 }
 
-define_command! {
-    doc: "`O,S,1` command. Enable salinity in the output string.",
-    OutputEnableSalinity, { "O,S,1".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`TDS,?` command. Returns a `TdsFactor` response. Current conductivity to total dissolved solids conversion factor.",
+    TdsConversionFactorState, { "TDS,?".to_string() }, 300,
+    resp: TdsFactor, { TdsFactor::parse(&resp) }
 }
 
-impl FromStr for OutputEnableSalinity {
+impl FromStr for TdsConversionFactorState {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,S,1" => Ok(OutputEnableSalinity),
+            "TDS,?" => Ok(TdsConversionFactorState),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,SG,0` command. Disable specific gravity in the output string.",
-    OutputDisableSpecificGravity, { "O,SG,0".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`*OK,0` command. Disable `*OK`/`*ER` response codes.",
+    ResponseCodeDisable, { "*OK,0".to_string() }, 300, Ack
 }
 
-impl FromStr for OutputDisableSpecificGravity {
+impl FromStr for ResponseCodeDisable {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,SG,0" => Ok(OutputDisableSpecificGravity),
+            "*OK,0" => Ok(ResponseCodeDisable),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,SG,1` command. Enable specific gravity in the output string.",
-    OutputEnableSpecificGravity, { "O,SG,1".to_string() }, 300, Ack
+define_ec_command! {
+    doc: "`*OK,1` command. Enable `*OK`/`*ER` response codes.",
+    ResponseCodeEnable, { "*OK,1".to_string() }, 300, Ack
 }
 
-impl FromStr for OutputEnableSpecificGravity {
+impl FromStr for ResponseCodeEnable {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,SG,1" => Ok(OutputEnableSpecificGravity),
+            "*OK,1" => Ok(ResponseCodeEnable),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`O,?` command. Returns an `OutputStringStatus` response. Displays the enabled parameters for the output string.",
-    OutputState, { "O,?".to_string() }, 300,
-    resp: OutputStringStatus, { OutputStringStatus::parse(&resp) }
+define_ec_command! {
+    doc: "`*OK,?` command. Returns a `ResponseCodeStatus` response. Whether `*OK`/`*ER` response codes are enabled.",
+    ResponseCodeState, { "*OK,?".to_string() }, 300,
+    resp: ResponseCodeStatus, { ResponseCodeStatus::parse(&resp) }
 }
 
-impl FromStr for OutputState {
+impl FromStr for ResponseCodeState {
     type Err = EzoError;
 
     fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
+        let supper = canonical_command(s);
         match supper.as_ref() {
-            "O,?" => Ok(OutputState),
+            "*OK,?" => Ok(ResponseCodeState),
             _ => Err(ErrorKind::CommandParse)?,
         }
     }
 }
 
-define_command! {
-    doc: "`T,t` command, where `t` is of type `f64`. Returns a `TemperatureCompensation` response. Temperature compensation.",
-    cmd: TemperatureCompensation(f64), { format!("T,{:.*}", 3, cmd) }, 300, Ack
+/// Implements `EcCommand` for commands from `ezo_common` that the chip only
+/// acknowledges.
+macro_rules! impl_ack_ec_command {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl EcCommand for $name {
+                fn parse_response(&self, _response: &str) -> Result<ResponseStatus, EzoError> {
+                    Ok(ResponseStatus::Ack)
+                }
+
+                fn max_response_len(&self) -> usize {
+                    ACK_RESPONSE_LEN
+                }
+
+                fn accepts_no_data(&self) -> bool {
+                    true
+                }
+            }
+        )*
+    };
 }
 
-impl FromStr for TemperatureCompensation {
-    type Err = EzoError;
+// The chip has no command to stop `Find`: any command ends the blinking.
+// `LedOn` and `LedOff` stop it, leaving the LED on or off.
+impl_ack_ec_command!(Find, LedOn, LedOff);
 
-    fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
-        if supper.starts_with("T,") {
-            let rest = supper.get(2..).unwrap();
-            let mut split = rest.split(',');
-            let value = match split.next() {
-                Some(n) => n.parse::<f64>().context(ErrorKind::CommandParse)?,
-                _ => return Err(ErrorKind::CommandParse)?,
-            };
-            match split.next() {
-                None => return Ok(TemperatureCompensation(value)),
-                _ => return Err(ErrorKind::CommandParse)?,
-            }
+impl VerifiedSetter for TemperatureCompensation {
+    type Query = CompensatedTemperatureValue;
+
+    fn query(&self) -> CompensatedTemperatureValue {
+        CompensatedTemperatureValue
+    }
+
+    fn verify(&self, response: &CompensationValue) -> Result<(), EzoError> {
+        if (response.0 - (self.0).0).abs() <= SETTING_TOLERANCE {
+            Ok(())
         } else {
-            return Err(ErrorKind::CommandParse)?;
+            Err(mismatch("temperature compensation", &self.0, response))
         }
     }
 }
 
-define_command! {
-    doc: "`T,?` command. Returns a `CompensationValue` response. Compensated temperature value.",
-    CompensatedTemperatureValue, { "T,?".to_string() }, 300,
-    resp: CompensationValue, { CompensationValue::parse(&resp) }
+impl VerifiedSetter for TdsConversionFactor {
+    type Query = TdsConversionFactorState;
+
+    fn query(&self) -> TdsConversionFactorState {
+        TdsConversionFactorState
+    }
+
+    fn verify(&self, response: &TdsFactor) -> Result<(), EzoError> {
+        if (response.0 - self.0).abs() <= SETTING_TOLERANCE {
+            Ok(())
+        } else {
+            Err(mismatch("TDS factor", &self.0, response))
+        }
+    }
 }
 
-impl FromStr for CompensatedTemperatureValue {
-    type Err = EzoError;
+impl VerifiedSetter for OutputConfigure {
+    type Query = OutputState;
 
-    fn from_str(s: &str) -> Result<Self, EzoError> {
-        let supper = s.to_uppercase();
-        match supper.as_ref() {
-            "T,?" => Ok(CompensatedTemperatureValue),
-            _ => Err(ErrorKind::CommandParse)?,
+    fn query(&self) -> OutputState {
+        OutputState
+    }
+
+    fn verify(&self, response: &OutputStringStatus) -> Result<(), EzoError> {
+        let status = match self.parameter {
+            OutputParameter::Conductivity => response.electric_conductivity,
+            OutputParameter::Tds => response.total_dissolved_solids,
+            OutputParameter::Salinity => response.salinity,
+            OutputParameter::SpecificGravity => response.specific_gravity,
+        };
+        if bool::from(status) == self.enable {
+            Ok(())
+        } else {
+            Err(mismatch(
+                "output",
+                &self.get_command_string(),
+                &format!("{:?}", response),
+            ))
         }
     }
 }
 
+/// Implements `VerifiedSetter` for setters whose query must read back a
+/// fixed response.
+macro_rules! impl_verified_setter {
+    ($($setter:ident => $query:ident, $setting:expr, $expected:expr);* $(;)?) => {
+        $(
+            impl VerifiedSetter for $setter {
+                type Query = $query;
+
+                fn query(&self) -> $query {
+                    $query
+                }
+
+                fn verify(&self, response: &<$query as Command>::Response) -> Result<(), EzoError> {
+                    if *response == $expected {
+                        Ok(())
+                    } else {
+                        Err(mismatch($setting, &$expected, response))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_verified_setter!(
+    ProbeTypePointOne => ProbeTypeState, "probe type", ProbeType::PointOne;
+    ProbeTypeOne => ProbeTypeState, "probe type", ProbeType::One;
+    ProbeTypeTen => ProbeTypeState, "probe type", ProbeType::Ten;
+    ResponseCodeEnable => ResponseCodeState, "response codes", ResponseCodeStatus::Enabled;
+    ResponseCodeDisable => ResponseCodeState, "response codes", ResponseCodeStatus::Disabled;
+);
+
+/// Implements `TryFrom<&str>` by delegating to the type's `FromStr`.
+macro_rules! impl_try_from_str {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<'a> TryFrom<&'a str> for $name {
+                type Error = EzoError;
+
+                fn try_from(s: &'a str) -> Result<Self, EzoError> {
+                    s.parse()
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_str!(
+    CalibrationState,
+    CalibrationDry,
+    CalibrationOnePoint,
+    CalibrationLow,
+    CalibrationHigh,
+    ProbeTypePointOne,
+    ProbeTypeOne,
+    ProbeTypeTen,
+    ProbeTypeState,
+    Reading,
+    OutputParameter,
+    OutputConfigure,
+    OutputDisableConductivity,
+    OutputEnableConductivity,
+    OutputDisableTds,
+    OutputEnableTds,
+    OutputDisableSalinity,
+    OutputEnableSalinity,
+    OutputDisableSpecificGravity,
+    OutputEnableSpecificGravity,
+    OutputState,
+    TemperatureCompensation,
+    CompensatedTemperatureValue,
+    ReadingWithTemperature,
+    TdsConversionFactor,
+    TdsConversionFactorState,
+    ResponseCodeDisable,
+    ResponseCodeEnable,
+    ResponseCodeState,
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::errors::{
+        device_error, truncated_response, unsupported_output, value_out_of_range, DeviceError,
+    };
+
+    #[test]
+    fn builds_raw_command() {
+        let cmd = RawCommand::new("Name,?").unwrap();
+        assert_eq!(cmd.get_command_string(), "Name,?");
+        assert_eq!(cmd.get_delay(), 300);
+        assert_eq!(cmd.max_response_len(), MAX_DATA);
+        assert!(!cmd.accepts_no_data());
+
+        let cmd = RawCommand::new("Plock,1")
+            .unwrap()
+            .with_response_delay(Duration::from_millis(900))
+            .with_expected_response(false);
+        assert_eq!(cmd.get_delay(), 900);
+        assert!(!cmd.expects_response());
+        assert_eq!(cmd.max_response_len(), ACK_RESPONSE_LEN);
+        assert!(cmd.accepts_no_data());
+    }
+
+    #[test]
+    fn refuses_raw_command_that_is_not_ascii_or_too_long() {
+        let err = RawCommand::new("").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandParse);
+        assert_eq!(value_out_of_range(&err).unwrap().value, 0.0);
+
+        let err = RawCommand::new(&"R".repeat(MAX_COMMAND_LEN + 1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandParse);
+        assert!(value_out_of_range(&err).is_some());
+        assert!(RawCommand::new(&"R".repeat(MAX_COMMAND_LEN)).is_ok());
+
+        for command in ["Name,sonde°", "R\0", "R\r"].iter() {
+            let err = RawCommand::new(command).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::CommandParse);
+            assert!(value_out_of_range(&err).is_none());
+        }
+    }
+
+    #[test]
+    fn find_and_led_commands_are_acknowledged() {
+        assert_eq!(Find.expected_response_kind(), ResponseType::Ack);
+        assert!(Find.accepts_no_data());
+        assert_eq!(LedOn.expected_response_kind(), ResponseType::Ack);
+        assert!(LedOn.accepts_no_data());
+        assert_eq!(LedOff.max_response_len(), ACK_RESPONSE_LEN);
+    }
 
     #[test]
     fn build_command_calibration_dry() {
@@ -734,6 +2794,90 @@ mod tests {
         assert_eq!(cmd, OutputEnableSpecificGravity);
     }
 
+    #[test]
+    fn build_command_output_configure() {
+        let cases = [
+            (OutputParameter::Conductivity, false, "O,EC,0"),
+            (OutputParameter::Conductivity, true, "O,EC,1"),
+            (OutputParameter::Tds, false, "O,TDS,0"),
+            (OutputParameter::Tds, true, "O,TDS,1"),
+            (OutputParameter::Salinity, false, "O,S,0"),
+            (OutputParameter::Salinity, true, "O,S,1"),
+            (OutputParameter::SpecificGravity, false, "O,SG,0"),
+            (OutputParameter::SpecificGravity, true, "O,SG,1"),
+        ];
+        for &(parameter, enable, expected) in cases.iter() {
+            let cmd = OutputConfigure { parameter, enable };
+            assert_eq!(cmd.get_command_string(), expected);
+            assert_eq!(cmd.get_delay(), 300);
+            assert_eq!(expected.parse::<OutputConfigure>().unwrap(), cmd);
+            assert_eq!(
+                expected.to_lowercase().parse::<OutputConfigure>().unwrap(),
+                cmd
+            );
+        }
+    }
+
+    #[test]
+    fn output_wrappers_match_output_configure() {
+        let configure =
+            |parameter, enable| OutputConfigure { parameter, enable }.get_command_string();
+        assert_eq!(
+            OutputEnableConductivity.get_command_string(),
+            configure(OutputParameter::Conductivity, true)
+        );
+        assert_eq!(
+            OutputDisableSpecificGravity.get_command_string(),
+            configure(OutputParameter::SpecificGravity, false)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_command_output_configure_yields_err() {
+        assert!("O,EC".parse::<OutputConfigure>().is_err());
+        assert!("O,EC,2".parse::<OutputConfigure>().is_err());
+        assert!("O,PH,1".parse::<OutputConfigure>().is_err());
+        assert!("O,EC,1,0".parse::<OutputConfigure>().is_err());
+        assert!("R".parse::<OutputConfigure>().is_err());
+    }
+
+    #[test]
+    fn maps_reading_values_by_output_parameter() {
+        let status = OutputStringStatus::parse("?O,EC,TDS,S").unwrap();
+        let reading = ProbeReading::ThreeParameters(12.5, 6.7, 0.01);
+        let map = reading.to_map(&status).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[&OutputParameter::Conductivity], 12.5);
+        assert_eq!(map[&OutputParameter::Tds], 6.7);
+        assert_eq!(map[&OutputParameter::Salinity], 0.01);
+        assert!(!map.contains_key(&OutputParameter::SpecificGravity));
+
+        let reading = ProbeReading::TwoParameters(12.5, 6.7);
+        let err = reading.to_map(&status).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseParse);
+    }
+
+    #[test]
+    fn diffs_output_string_status_into_commands() {
+        let current = OutputStringStatus::new()
+            .with_conductivity(true)
+            .with_tds(true);
+        assert!(current.diff_commands(&current).is_empty());
+
+        let target = current.with_salinity(true);
+        let cmds = current.diff_commands(&target);
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].get_command_string(), "O,S,1");
+
+        let target = OutputStringStatus::new().with_specific_gravity(true);
+        let cmds: Vec<String> = current
+            .diff_commands(&target)
+            .iter()
+            .map(|cmd| cmd.get_command_string())
+            .collect();
+        assert_eq!(cmds, vec!["O,EC,0", "O,TDS,0", "O,SG,1"]);
+    }
+
     #[test]
     fn build_command_output_state() {
         let cmd = OutputState;
@@ -766,20 +2910,95 @@ mod tests {
         assert_eq!(cmd, Reading);
     }
 
+    #[test]
+    fn parses_command_aliases() {
+        assert_eq!("read".parse::<Reading>().unwrap(), Reading);
+        assert_eq!("Reading".parse::<Reading>().unwrap(), Reading);
+        assert_eq!("cal,dry".parse::<CalibrationDry>().unwrap(), CalibrationDry);
+        assert_eq!(
+            "calibrate,dry".parse::<CalibrationDry>().unwrap(),
+            CalibrationDry
+        );
+        assert_eq!(
+            "Calibration,?".parse::<CalibrationState>().unwrap(),
+            CalibrationState
+        );
+        assert_eq!(
+            "calibrate,low,12880".parse::<CalibrationLow>().unwrap(),
+            CalibrationLow(12_880.0)
+        );
+        assert_eq!("probe,10.0".parse::<ProbeTypeTen>().unwrap(), ProbeTypeTen);
+        assert_eq!("probe,?".parse::<ProbeTypeState>().unwrap(), ProbeTypeState);
+        assert_eq!("output,?".parse::<OutputState>().unwrap(), OutputState);
+        assert_eq!(
+            "output,ec,1".parse::<OutputEnableConductivity>().unwrap(),
+            OutputEnableConductivity
+        );
+        assert_eq!(
+            "temp,19.5".parse::<TemperatureCompensation>().unwrap(),
+            TemperatureCompensation(Celsius(19.5))
+        );
+        assert_eq!(
+            "temperature,?"
+                .parse::<CompensatedTemperatureValue>()
+                .unwrap(),
+            CompensatedTemperatureValue
+        );
+    }
+
+    #[test]
+    fn aliases_only_replace_the_command_word() {
+        assert!("readings".parse::<Reading>().is_err());
+        assert!("cal,read".parse::<CalibrationDry>().is_err());
+        assert!("output".parse::<Reading>().is_err());
+    }
+
     #[test]
     fn build_command_temperature_compensation() {
-        let cmd = TemperatureCompensation(19.5);
+        let cmd = TemperatureCompensation(Celsius(19.5));
         assert_eq!(cmd.get_command_string(), "T,19.500");
         assert_eq!(cmd.get_delay(), 300);
+
+        let cmd = TemperatureCompensation(19.5.into());
+        assert_eq!(cmd.get_command_string(), "T,19.500");
+        let cmd = TemperatureCompensation(Celsius(-2.34567));
+        assert_eq!(cmd.get_command_string(), "T,-2.346");
     }
 
     #[test]
     fn parse_case_insensitive_command_temperature_compensation() {
         let cmd = "t,0".parse::<TemperatureCompensation>().unwrap();
-        assert_eq!(cmd, TemperatureCompensation(0_f64));
+        assert_eq!(cmd, TemperatureCompensation(Celsius(0.0)));
 
         let cmd = "T,10.5".parse::<TemperatureCompensation>().unwrap();
-        assert_eq!(cmd, TemperatureCompensation(10.5));
+        assert_eq!(cmd, TemperatureCompensation(Celsius(10.5)));
+    }
+
+    #[test]
+    fn parse_command_temperature_compensation_with_unit_suffix() {
+        let cmd = "T,25C".parse::<TemperatureCompensation>().unwrap();
+        assert_eq!(cmd, TemperatureCompensation(Celsius(25.0)));
+
+        let cmd = "T,25°c".parse::<TemperatureCompensation>().unwrap();
+        assert_eq!(cmd, TemperatureCompensation(Celsius(25.0)));
+
+        let cmd = "T,77F".parse::<TemperatureCompensation>().unwrap();
+        assert_eq!(cmd, TemperatureCompensation(Celsius(25.0)));
+        assert_eq!(cmd.get_command_string(), "T,25.000");
+
+        let cmd = "rt,32°F".parse::<ReadingWithTemperature>().unwrap();
+        assert_eq!(cmd, ReadingWithTemperature(Celsius(0.0)));
+
+        let cmd = "T,25".parse::<TemperatureCompensation>().unwrap();
+        assert_eq!(cmd, TemperatureCompensation(Celsius(25.0)));
+    }
+
+    #[test]
+    fn parse_command_temperature_compensation_with_unknown_suffix_yields_err() {
+        for input in ["T,25K", "T,25CF", "T,25FC", "T,F", "T,25 C", "T,-500F"].iter() {
+            let err = input.parse::<TemperatureCompensation>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::CommandParse);
+        }
     }
 
     #[test]
@@ -809,4 +3028,450 @@ mod tests {
         let cmd = "T,?".parse::<CompensatedTemperatureValue>().unwrap();
         assert_eq!(cmd, CompensatedTemperatureValue);
     }
+
+    #[test]
+    fn build_command_reading_with_temperature() {
+        let cmd = ReadingWithTemperature(Celsius(19.5));
+        assert_eq!(cmd.get_command_string(), "RT,19.500");
+        assert_eq!(cmd.get_delay(), 600);
+
+        let cmd = ReadingWithTemperature(19.5.into());
+        assert_eq!(cmd.get_command_string(), "RT,19.500");
+    }
+
+    #[test]
+    fn parse_case_insensitive_command_reading_with_temperature() {
+        let cmd = "rt,19.5".parse::<ReadingWithTemperature>().unwrap();
+        assert_eq!(cmd, ReadingWithTemperature(Celsius(19.5)));
+
+        let cmd = "RT,0".parse::<ReadingWithTemperature>().unwrap();
+        assert_eq!(cmd, ReadingWithTemperature(Celsius(0.0)));
+    }
+
+    #[test]
+    fn parse_invalid_command_reading_with_temperature_yields_err() {
+        assert!("RT,".parse::<ReadingWithTemperature>().is_err());
+        assert!("RT,$".parse::<ReadingWithTemperature>().is_err());
+        assert!("RT,19.5,1".parse::<ReadingWithTemperature>().is_err());
+        assert!("R,19.5".parse::<ReadingWithTemperature>().is_err());
+    }
+
+    #[test]
+    fn build_command_tds_conversion_factor() {
+        let cmd = TdsConversionFactor(0.54);
+        assert_eq!(cmd.get_command_string(), "TDS,0.54");
+        assert_eq!(cmd.get_delay(), 300);
+    }
+
+    #[test]
+    fn parse_case_insensitive_command_tds_conversion_factor() {
+        let cmd = "tds,0.5".parse::<TdsConversionFactor>().unwrap();
+        assert_eq!(cmd, TdsConversionFactor(0.5));
+
+        let cmd = "TDS,0.96".parse::<TdsConversionFactor>().unwrap();
+        assert_eq!(cmd, TdsConversionFactor(0.96));
+    }
+
+    #[test]
+    fn parse_invalid_command_tds_conversion_factor_yields_err() {
+        let cmd = "TDS,".parse::<TdsConversionFactor>();
+        assert!(cmd.is_err());
+
+        let cmd = "TDS,0.5a".parse::<TdsConversionFactor>();
+        assert!(cmd.is_err());
+
+        let cmd = "TDS,0.001".parse::<TdsConversionFactor>();
+        assert!(cmd.is_err());
+
+        let cmd = "TDS,1.5".parse::<TdsConversionFactor>();
+        assert!(cmd.is_err());
+    }
+
+    #[test]
+    fn try_from_str_matches_from_str() {
+        let cmd = CalibrationOnePoint::try_from("CAL,5.0").unwrap();
+        assert_eq!(cmd, CalibrationOnePoint(5.0));
+
+        let cmd = Reading::try_from("r").unwrap();
+        assert_eq!(cmd, Reading);
+
+        let cmd = OutputConfigure::try_from("O,SG,1").unwrap();
+        assert_eq!(cmd, "O,SG,1".parse::<OutputConfigure>().unwrap());
+
+        let err = TdsConversionFactor::try_from("TDS,2").unwrap_err();
+        assert!(value_out_of_range(&err).is_some());
+        assert!(ProbeTypeState::try_from("K,1").is_err());
+    }
+
+    #[test]
+    fn parse_out_of_range_command_values_yields_value_out_of_range() {
+        let err = "TDS,1.5".parse::<TdsConversionFactor>().unwrap_err();
+        assert_eq!(
+            value_out_of_range(&err),
+            Some(ValueOutOfRange {
+                value: 1.5,
+                min: 0.01,
+                max: 1.0
+            })
+        );
+
+        let err = "T,-300".parse::<TemperatureCompensation>().unwrap_err();
+        assert_eq!(value_out_of_range(&err).unwrap().value, -300.);
+
+        let err = "RT,-273.2".parse::<ReadingWithTemperature>().unwrap_err();
+        assert!(value_out_of_range(&err).is_some());
+
+        let err = "T,1a21.43".parse::<TemperatureCompensation>().unwrap_err();
+        assert_eq!(value_out_of_range(&err), None);
+
+        assert!("T,-273.15".parse::<TemperatureCompensation>().is_ok());
+    }
+
+    #[test]
+    fn build_command_tds_conversion_factor_state() {
+        let cmd = TdsConversionFactorState;
+        assert_eq!(cmd.get_command_string(), "TDS,?");
+        assert_eq!(cmd.get_delay(), 300);
+    }
+
+    #[test]
+    fn parse_case_insensitive_command_tds_conversion_factor_state() {
+        let cmd = "tds,?".parse::<TdsConversionFactorState>().unwrap();
+        assert_eq!(cmd, TdsConversionFactorState);
+
+        let cmd = "TDS,?".parse::<TdsConversionFactorState>().unwrap();
+        assert_eq!(cmd, TdsConversionFactorState);
+    }
+
+    #[test]
+    fn delay_override_keeps_command_and_parser() {
+        let cmd = CalibrationHigh(12_880.).with_delay(300);
+        assert_eq!(cmd.get_command_string(), "CAL,HIGH,12880.00");
+        assert_eq!(cmd.get_delay(), 300);
+        assert_eq!(cmd.command.get_delay(), 800);
+
+        let cmd = Reading.with_delay(450);
+        assert_eq!(cmd.get_delay(), 450);
+        assert_eq!(
+            cmd.parse_response("12.5").unwrap(),
+            ProbeReading::OneParameter(12.5)
+        );
+    }
+
+    #[test]
+    fn build_command_response_code_disable() {
+        let cmd = ResponseCodeDisable;
+        assert_eq!(cmd.get_command_string(), "*OK,0");
+        assert_eq!(cmd.get_delay(), 300);
+    }
+
+    #[test]
+    fn parse_case_insensitive_command_response_code_disable() {
+        let cmd = "*ok,0".parse::<ResponseCodeDisable>().unwrap();
+        assert_eq!(cmd, ResponseCodeDisable);
+
+        let cmd = "*OK,0".parse::<ResponseCodeDisable>().unwrap();
+        assert_eq!(cmd, ResponseCodeDisable);
+    }
+
+    #[test]
+    fn build_command_response_code_enable() {
+        let cmd = ResponseCodeEnable;
+        assert_eq!(cmd.get_command_string(), "*OK,1");
+        assert_eq!(cmd.get_delay(), 300);
+    }
+
+    #[test]
+    fn parse_case_insensitive_command_response_code_enable() {
+        let cmd = "*ok,1".parse::<ResponseCodeEnable>().unwrap();
+        assert_eq!(cmd, ResponseCodeEnable);
+
+        let cmd = "*OK,1".parse::<ResponseCodeEnable>().unwrap();
+        assert_eq!(cmd, ResponseCodeEnable);
+    }
+
+    #[test]
+    fn build_command_response_code_state() {
+        let cmd = ResponseCodeState;
+        assert_eq!(cmd.get_command_string(), "*OK,?");
+        assert_eq!(cmd.get_delay(), 300);
+    }
+
+    #[test]
+    fn parse_case_insensitive_command_response_code_state() {
+        let cmd = "*ok,?".parse::<ResponseCodeState>().unwrap();
+        assert_eq!(cmd, ResponseCodeState);
+
+        let cmd = "*OK,?".parse::<ResponseCodeState>().unwrap();
+        assert_eq!(cmd, ResponseCodeState);
+    }
+
+    #[test]
+    fn ack_commands_reserve_short_responses() {
+        assert_eq!(CalibrationDry.max_response_len(), ACK_RESPONSE_LEN);
+        assert_eq!(
+            TemperatureCompensation(Celsius(19.5)).max_response_len(),
+            ACK_RESPONSE_LEN
+        );
+        assert_eq!(
+            OutputConfigure {
+                parameter: OutputParameter::Tds,
+                enable: true
+            }
+            .max_response_len(),
+            ACK_RESPONSE_LEN
+        );
+        assert_eq!(Reading.max_response_len(), MAX_DATA);
+        assert_eq!(CalibrationState.max_response_len(), MAX_DATA);
+        assert_eq!(
+            CalibrationDry.with_delay(100).max_response_len(),
+            ACK_RESPONSE_LEN
+        );
+        assert_eq!(response_len(0), 2);
+        assert_eq!(response_len(1_000), MAX_DATA);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn rounds_bus_timeout_up_to_10_ms_units() {
+        assert_eq!(bus_timeout_units(Duration::from_millis(0)), 0);
+        assert_eq!(bus_timeout_units(Duration::from_millis(1)), 1);
+        assert_eq!(bus_timeout_units(Duration::from_millis(10)), 1);
+        assert_eq!(bus_timeout_units(Duration::from_millis(250)), 25);
+        assert_eq!(bus_timeout_units(Duration::from_secs(2)), 200);
+    }
+
+    #[test]
+    fn commands_report_expected_response_kind() {
+        assert_eq!(CalibrationDry.expected_response_kind(), ResponseType::Ack);
+        assert_eq!(
+            CalibrationState.expected_response_kind(),
+            ResponseType::Calibration
+        );
+        assert_eq!(Reading.expected_response_kind(), ResponseType::Reading);
+        assert_eq!(
+            Reading.with_delay(300).expected_response_kind(),
+            ResponseType::Reading
+        );
+        assert_eq!(OutputState.expected_response_kind(), ResponseType::Output);
+
+        let cmds: Vec<Box<dyn DynCommand>> = vec![
+            Box::new(ProbeTypeState),
+            Box::new(TemperatureCompensation(Celsius(19.5))),
+            Box::new(CompensatedTemperatureValue),
+        ];
+        let kinds: Vec<ResponseType> = cmds.iter().map(|cmd| cmd.response_type()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ResponseType::ProbeType,
+                ResponseType::Ack,
+                ResponseType::Compensation
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_sensor_config_from_json() {
+        let json = r#"{
+            "probe": "Ten",
+            "output": { "electric_conductivity": "On", "salinity": "On" },
+            "tds_factor": 0.5,
+            "temperature": 25.0
+        }"#;
+        let config: SensorConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.name, None);
+        assert_eq!(config.probe, ProbeType::Ten);
+        assert_eq!(
+            config.output,
+            OutputStringStatus::new()
+                .with_conductivity(true)
+                .with_salinity(true)
+        );
+        assert_eq!(config.temperature, Celsius(25.0));
+    }
+
+    #[test]
+    fn builds_import_commands_from_valid_export() {
+        let chunks: Vec<String> = vec!["596F75206172".into(), "6520616D617A".into(), "696E".into()];
+        let info = ExportedInfo {
+            lines: 3,
+            total_bytes: 28,
+        };
+        let cmds = import_commands(&chunks, &info).unwrap();
+        let cmds: Vec<String> = cmds.iter().map(|cmd| cmd.get_command_string()).collect();
+        assert_eq!(
+            cmds,
+            vec!["IMPORT,596F75206172", "IMPORT,6520616D617A", "IMPORT,696E"]
+        );
+    }
+
+    #[test]
+    fn invalid_import_chunks_yield_err() {
+        let err = validate_import_chunk("0123456789ABC").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandParse);
+        assert_eq!(value_out_of_range(&err).unwrap().value, 13.);
+
+        assert!(validate_import_chunk("").is_err());
+        assert!(validate_import_chunk("12,34").is_err());
+        assert!(validate_import_chunk("0123456789AB").is_ok());
+
+        let chunks: Vec<String> = vec!["0123456789ABC".into()];
+        let info = ExportedInfo {
+            lines: 1,
+            total_bytes: 13,
+        };
+        assert!(import_commands(&chunks, &info).is_err());
+
+        let chunks: Vec<String> = vec!["0123".into(), "4567".into()];
+        let info = ExportedInfo {
+            lines: 3,
+            total_bytes: 8,
+        };
+        assert!(import_commands(&chunks, &info).is_err());
+    }
+
+    #[test]
+    fn parse_response_of_ack_command_yields_ack() {
+        let resp = CalibrationDry.parse_response("").unwrap();
+        assert_eq!(resp, ResponseStatus::Ack);
+
+        let resp = TemperatureCompensation(Celsius(19.5))
+            .parse_response("")
+            .unwrap();
+        assert_eq!(resp, ResponseStatus::Ack);
+    }
+
+    #[test]
+    fn parse_response_of_query_command_uses_response_parser() {
+        let resp = Reading.parse_response("12.5,0.0").unwrap();
+        assert_eq!(resp, ProbeReading::TwoParameters(12.5, 0.0));
+
+        let resp = ReadingWithTemperature(Celsius(19.5))
+            .parse_response("12.5")
+            .unwrap();
+        assert_eq!(resp, ProbeReading::OneParameter(12.5));
+
+        let resp = CalibrationState.parse_response("?CAL,2").unwrap();
+        assert_eq!(resp, CalibrationStatus::TwoPoint);
+
+        assert!(ProbeTypeState.parse_response("?K,4").is_err());
+    }
+
+    #[test]
+    fn decodes_response_data_by_response_code() {
+        let resp = response_from_data(b"\x0112.5,0.0\0\0").unwrap();
+        assert_eq!(resp, "12.5,0.0");
+
+        let resp = response_from_data(b"\x01\0\0").unwrap();
+        assert_eq!(resp, "");
+
+        let resp = response_from_data(b"\x0112.5\r*OK\0").unwrap();
+        assert_eq!(resp, "12.5");
+
+        let err = response_from_data(b"\x01*ER\0").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+
+        let err = response_from_data(b"\x02\0").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+        assert!(device_error(&err).unwrap().is_failure());
+
+        let err = response_from_data(b"\xfe\0").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PendingResponse);
+        assert!(device_error(&err).unwrap().is_pending());
+
+        let err = response_from_data(b"\xff\0").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NoDataExpectedResponse);
+        assert_eq!(device_error(&err), Some(DeviceError { code: 255 }));
+
+        let err = response_from_data(b"\x0112.5").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MalformedResponse);
+        assert_eq!(device_error(&err), None);
+        assert_eq!(truncated_response(&err).unwrap().partial, "12.5");
+
+        let err = response_from_data(b"\x07\0").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MalformedResponse);
+        assert_eq!(device_error(&err), None);
+    }
+
+    #[test]
+    fn validates_calibration_values_for_probe_type() {
+        assert!(CalibrationLow(84.)
+            .validate_for_probe(ProbeType::PointOne)
+            .is_ok());
+        assert!(CalibrationHigh(1413.)
+            .validate_for_probe(ProbeType::PointOne)
+            .is_ok());
+        assert!(CalibrationLow(12_880.)
+            .validate_for_probe(ProbeType::One)
+            .is_ok());
+        assert!(CalibrationHigh(80_000.)
+            .validate_for_probe(ProbeType::One)
+            .is_ok());
+        assert!(CalibrationOnePoint(150_000.)
+            .validate_for_probe(ProbeType::Ten)
+            .is_ok());
+    }
+
+    #[test]
+    fn validating_calibration_values_outside_probe_range_yields_err() {
+        let err = CalibrationLow(84.)
+            .validate_for_probe(ProbeType::Ten)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandParse);
+        let range = value_out_of_range(&err).unwrap();
+        assert_eq!(range.value, 84.);
+        assert_eq!((range.min, range.max), (1_288., 1_000_000.));
+
+        assert!(CalibrationHigh(150_000.)
+            .validate_for_probe(ProbeType::PointOne)
+            .is_err());
+        assert!(CalibrationOnePoint(84.)
+            .validate_for_probe(ProbeType::One)
+            .is_err());
+        assert!(CalibrationOnePoint(-1.)
+            .validate_for_probe(ProbeType::PointOne)
+            .is_err());
+    }
+
+    #[test]
+    fn validates_specific_gravity_output_for_probe_type() {
+        assert!(OutputEnableSpecificGravity
+            .validate_for_probe(ProbeType::One)
+            .is_ok());
+        assert!(OutputEnableSpecificGravity
+            .validate_for_probe(ProbeType::Ten)
+            .is_ok());
+
+        let err = OutputEnableSpecificGravity
+            .validate_for_probe(ProbeType::PointOne)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandParse);
+        let unsupported = unsupported_output(&err).unwrap();
+        assert_eq!(unsupported.parameter, OutputParameter::SpecificGravity);
+        assert_eq!(unsupported.probe, ProbeType::PointOne);
+    }
+
+    #[test]
+    fn only_enabling_specific_gravity_is_checked_for_probe_type() {
+        let disable = OutputConfigure {
+            parameter: OutputParameter::SpecificGravity,
+            enable: false,
+        };
+        assert!(disable.validate_for_probe(ProbeType::PointOne).is_ok());
+
+        let enable = OutputConfigure {
+            enable: true,
+            ..disable
+        };
+        assert!(enable.validate_for_probe(ProbeType::PointOne).is_err());
+        for &parameter in &[
+            OutputParameter::Conductivity,
+            OutputParameter::Tds,
+            OutputParameter::Salinity,
+        ] {
+            assert!(parameter.validate_for_probe(ProbeType::PointOne).is_ok());
+        }
+    }
 }
@@ -0,0 +1,303 @@
+//! Host-side conversions from electrical conductivity to derived metrics,
+//! and corrections of conductivity readings.
+//!
+//! The EZO EC chip reports conductivity compensated to 25°C, so these
+//! helpers evaluate their formulas at that reference temperature.
+
+use super::response::{LabeledReading, ProbeMetric, ProbeReading};
+
+/// Conductivity of standard seawater (S = 35, t = 15°C), in µS/cm.
+const STANDARD_SEAWATER_EC: f64 = 42_914.0;
+
+/// Reference temperature of the compensated readings, in °C.
+const REFERENCE_TEMPERATURE: f64 = 25.0;
+
+/// Lowest salinity, in PSU, for which the practical salinity scale is valid.
+const MIN_SEAWATER_SALINITY: f64 = 2.0;
+
+/// Ratio of salinity to chlorinity (UNESCO 1966), `S = 1.80655 Cl`.
+const SALINITY_CHLORINITY_RATIO: f64 = 1.806_55;
+
+/// Offset and ratio of Knudsen's (1902) total salt from chlorinity, in
+/// g/kg, `S = 0.030 + 1.805 Cl`.
+const KNUDSEN_SALT_OFFSET: f64 = 0.030;
+const KNUDSEN_SALT_RATIO: f64 = 1.805;
+
+/// Ratio of reference salinity (g/kg) to practical salinity, from TEOS-10.
+const REFERENCE_SALINITY_RATIO: f64 = 35.165_04 / 35.0;
+
+/// Evaluates a polynomial with coefficients in ascending order of power.
+fn polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+/// Converts conductivity (µS/cm) into total dissolved solids (ppm), using
+/// the given conversion `factor`.
+pub fn ec_to_tds(ec_us_cm: f64, factor: f64) -> f64 {
+    ec_us_cm * factor
+}
+
+/// Converts conductivity (µS/cm) into salinity (PSU), using the Practical
+/// Salinity Scale of 1978 (PSS-78).
+pub fn ec_to_salinity(ec_us_cm: f64) -> f64 {
+    let t = REFERENCE_TEMPERATURE;
+    let rt = polynomial(
+        &[6.766097e-1, 2.00564e-2, 1.104259e-4, -6.9698e-7, 1.0031e-9],
+        t,
+    );
+    let ratio = ec_us_cm / STANDARD_SEAWATER_EC / rt;
+    if ratio <= 0.0 {
+        return 0.0;
+    }
+    let x = ratio.sqrt();
+    let salinity = polynomial(&[0.0080, -0.1692, 25.3851, 14.0941, -7.0261, 2.7081], x);
+    let delta = (t - 15.0) / (1.0 + 0.0162 * (t - 15.0))
+        * polynomial(&[0.0005, -0.0056, -0.0066, -0.0375, 0.0636, -0.0144], x);
+    salinity + delta
+}
+
+/// Converts conductivity (µS/cm) into the specific gravity of seawater,
+/// using the one-atmosphere equation of state (EOS-80).
+///
+/// Returns `None` when the corresponding salinity is below the valid
+/// seawater range.
+pub fn ec_to_specific_gravity(ec_us_cm: f64) -> Option<f64> {
+    let salinity = ec_to_salinity(ec_us_cm);
+    if salinity < MIN_SEAWATER_SALINITY {
+        return None;
+    }
+    Some(seawater_density(salinity) / seawater_density(0.0))
+}
+
+/// Density of seawater (kg/m³) of the given salinity (PSU), at the reference
+/// temperature, from the one-atmosphere equation of state (EOS-80).
+fn seawater_density(salinity: f64) -> f64 {
+    let t = REFERENCE_TEMPERATURE;
+    let pure_water = polynomial(
+        &[
+            999.842594,
+            6.793952e-2,
+            -9.095290e-3,
+            1.001685e-4,
+            -1.120083e-6,
+            6.536332e-9,
+        ],
+        t,
+    );
+    let a = polynomial(
+        &[8.24493e-1, -4.0899e-3, 7.6438e-5, -8.2467e-7, 5.3875e-9],
+        t,
+    );
+    let b = polynomial(&[-5.72466e-3, 1.0227e-4, -1.6546e-6], t);
+    let c = 4.8314e-4;
+    pure_water + a * salinity + b * salinity.powf(1.5) + c * salinity * salinity
+}
+
+/// Converts practical salinity (PSU) into chlorinity (g/kg), inverting
+/// `S = 1.80655 Cl`.
+fn psu_to_chlorinity(psu: f64) -> f64 {
+    psu / SALINITY_CHLORINITY_RATIO
+}
+
+/// Total salt (g/kg) of water with the given chlorinity (g/kg), from
+/// Knudsen's relation.
+fn knudsen_total_salt(chlorinity: f64) -> f64 {
+    KNUDSEN_SALT_OFFSET + KNUDSEN_SALT_RATIO * chlorinity
+}
+
+/// Converts practical salinity (PSU) into the mass concentration of total
+/// salt (mg/L).
+///
+/// Chlorinity is derived from the salinity, and total salt (g/kg) from the
+/// chlorinity with Knudsen's relation, `S = 0.030 + 1.805 Cl`. Its constant
+/// term puts any positive salinity at 30 mg/kg of salt or more, so very
+/// fresh samples read high. Total salt is then scaled by the EOS-80 density
+/// at the reference temperature, as g/kg times kg/m³ is mg/L.
+pub fn psu_to_mg_per_l(psu: f64) -> f64 {
+    if psu <= 0.0 {
+        return 0.0;
+    }
+    knudsen_total_salt(psu_to_chlorinity(psu)) * seawater_density(psu)
+}
+
+/// Converts practical salinity (PSU), as reported by the chip, into TEOS-10
+/// absolute salinity (g/kg).
+///
+/// Uses the reference-composition approximation, `SA = SR + δSA`, where the
+/// reference salinity `SR` scales practical salinity by 35.16504/35. The
+/// absolute salinity anomaly, `delta_sa` in g/kg, depends on where the water
+/// was sampled; pass `0.0` for standard seawater.
+pub fn practical_to_absolute_salinity(psu: f64, delta_sa: f64) -> f64 {
+    psu * REFERENCE_SALINITY_RATIO + delta_sa
+}
+
+/// Absolute salinity (g/kg) of the salinity value in `reading`, or `None`
+/// when the reading doesn't include salinity. See
+/// `practical_to_absolute_salinity`.
+pub fn absolute_salinity(reading: &LabeledReading, delta_sa: f64) -> Option<f64> {
+    reading.metrics().iter().find_map(|metric| match *metric {
+        ProbeMetric::Salinity(psu) => Some(practical_to_absolute_salinity(psu, delta_sa)),
+        _ => None,
+    })
+}
+
+/// Host-side linear correction of conductivity, for probes whose response
+/// needs adjusting beyond the chip's own calibration.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EcCalibrationCurve {
+    pub slope: f64,
+    pub offset: f64,
+}
+
+impl EcCalibrationCurve {
+    /// Curve that leaves readings unchanged.
+    pub fn identity() -> EcCalibrationCurve {
+        EcCalibrationCurve {
+            slope: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    /// Corrects a single conductivity value, in µS/cm.
+    pub fn correct(&self, ec_us_cm: f64) -> f64 {
+        ec_us_cm * self.slope + self.offset
+    }
+
+    /// Corrects the conductivity of `reading`, leaving its other values
+    /// unchanged.
+    ///
+    /// The chip reports conductivity first, so this assumes the reading comes
+    /// from an output string with conductivity enabled.
+    pub fn apply(&self, reading: ProbeReading) -> ProbeReading {
+        match reading {
+            ProbeReading::None => ProbeReading::None,
+            ProbeReading::OneParameter(ec) => ProbeReading::OneParameter(self.correct(ec)),
+            ProbeReading::TwoParameters(ec, b) => ProbeReading::TwoParameters(self.correct(ec), b),
+            ProbeReading::ThreeParameters(ec, b, c) => {
+                ProbeReading::ThreeParameters(self.correct(ec), b, c)
+            }
+            ProbeReading::FourParameters(ec, b, c, d) => {
+                ProbeReading::FourParameters(self.correct(ec), b, c, d)
+            }
+        }
+    }
+}
+
+impl Default for EcCalibrationCurve {
+    fn default() -> EcCalibrationCurve {
+        EcCalibrationCurve::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 0.001;
+
+    #[test]
+    fn converts_conductivity_to_total_dissolved_solids() {
+        assert!((ec_to_tds(1413.0, 0.54) - 763.02).abs() < EPSILON);
+        assert!((ec_to_tds(12_880.0, 0.5) - 6_440.0).abs() < EPSILON);
+        assert_eq!(ec_to_tds(0.0, 0.54), 0.0);
+    }
+
+    #[test]
+    fn converts_conductivity_to_salinity() {
+        // Standard seawater, S = 35, at the 25°C reference temperature.
+        assert!((ec_to_salinity(53_064.766) - 35.0).abs() < EPSILON);
+        assert!((ec_to_salinity(50_000.0) - 32.7375).abs() < EPSILON);
+        assert!((ec_to_salinity(12_880.0) - 7.3931).abs() < EPSILON);
+        assert_eq!(ec_to_salinity(0.0), 0.0);
+    }
+
+    #[test]
+    fn converts_conductivity_to_specific_gravity() {
+        let sg = ec_to_specific_gravity(53_064.766).unwrap();
+        assert!((sg - 1.02637).abs() < EPSILON);
+
+        let sg = ec_to_specific_gravity(50_000.0).unwrap();
+        assert!((sg - 1.02466).abs() < EPSILON);
+    }
+
+    #[test]
+    fn specific_gravity_below_seawater_range_is_none() {
+        assert_eq!(ec_to_specific_gravity(1413.0), None);
+        assert_eq!(ec_to_specific_gravity(0.0), None);
+    }
+
+    #[test]
+    fn converts_salinity_to_chlorinity() {
+        // UNESCO 1966: standard seawater of chlorinity 19.374 has S = 35.
+        assert!((psu_to_chlorinity(35.0) - 19.374).abs() < EPSILON);
+        assert_eq!(psu_to_chlorinity(0.0), 0.0);
+    }
+
+    #[test]
+    fn converts_chlorinity_to_total_salt() {
+        // Knudsen (1902): chlorinity 19.374 has 35.000 g/kg of salt.
+        assert!((knudsen_total_salt(19.374) - 35.0).abs() < EPSILON);
+        assert_eq!(knudsen_total_salt(0.0), 0.030);
+    }
+
+    #[test]
+    fn converts_salinity_to_mass_concentration() {
+        // EOS-80 check value: 1023.343 kg/m³ for S = 35 at 25°C.
+        assert!((psu_to_mg_per_l(35.0) - 35_816.977).abs() < EPSILON);
+        assert!((psu_to_mg_per_l(1.0) - 1_026.881).abs() < EPSILON);
+        assert!((psu_to_mg_per_l(0.5) - 528.208).abs() < EPSILON);
+        assert!((psu_to_mg_per_l(0.01) - 39.874).abs() < EPSILON);
+        assert_eq!(psu_to_mg_per_l(0.0), 0.0);
+        assert_eq!(psu_to_mg_per_l(-1.0), 0.0);
+    }
+
+    #[test]
+    fn identity_curve_leaves_readings_unchanged() {
+        let curve = EcCalibrationCurve::default();
+        let reading = ProbeReading::TwoParameters(1413.0, 763.0);
+        assert_eq!(curve.apply(reading), reading);
+        assert_eq!(curve.apply(ProbeReading::None), ProbeReading::None);
+    }
+
+    #[test]
+    fn corrects_conductivity_only() {
+        let curve = EcCalibrationCurve {
+            slope: 1.1,
+            offset: -20.0,
+        };
+        match curve.apply(ProbeReading::ThreeParameters(1000.0, 540.0, 0.5)) {
+            ProbeReading::ThreeParameters(ec, tds, s) => {
+                assert!((ec - 1080.0).abs() < EPSILON);
+                assert_eq!(tds, 540.0);
+                assert_eq!(s, 0.5);
+            }
+            other => panic!("unexpected reading {:?}", other),
+        }
+        assert!((curve.correct(0.0) + 20.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn converts_practical_to_absolute_salinity() {
+        // Reference salinity of standard seawater, from TEOS-10.
+        assert!((practical_to_absolute_salinity(35.0, 0.0) - 35.16504).abs() < EPSILON);
+        // gsw_SR_from_SP(34.5) = 34.662682.
+        assert!((practical_to_absolute_salinity(34.5, 0.0) - 34.662682).abs() < EPSILON);
+        assert!((practical_to_absolute_salinity(35.0, 0.0066) - 35.17164).abs() < EPSILON);
+        assert_eq!(practical_to_absolute_salinity(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn converts_salinity_of_labeled_reading() {
+        use crate::response::OutputStringStatus;
+
+        let status = OutputStringStatus::parse("?O,EC,S").unwrap();
+        let reading = ProbeReading::TwoParameters(53_064.766, 35.0);
+        let labeled = LabeledReading::new(&reading, &status).unwrap();
+        let sa = absolute_salinity(&labeled, 0.0).unwrap();
+        assert!((sa - 35.16504).abs() < EPSILON);
+
+        let status = OutputStringStatus::parse("?O,EC").unwrap();
+        let labeled = LabeledReading::new(&ProbeReading::OneParameter(1413.0), &status).unwrap();
+        assert_eq!(absolute_salinity(&labeled, 0.0), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Stand-ins for the `ezo_common` items that the response parsers and the
+//! bus exchanges need, for builds without `std`, where `ezo_common` isn't
+//! available.
+//!
+//! They mirror the upstream names, so the parsers read the same with or
+//! without `std`. Errors only carry their kind, not their cause.
+use core::fmt;
+
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+use alloc::string::String;
+
+/// Kinds of errors the response parsers and the bus exchanges can yield.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The command could not be turned into bytes for the bus.
+    CommandParse,
+    /// The response could not be parsed.
+    ResponseParse,
+    /// The response was not a nul-terminated ASCII string.
+    MalformedResponse,
+    /// The chip answered with an error.
+    DeviceErrorResponse,
+    /// The chip was still processing the command.
+    PendingResponse,
+    /// The chip had no data to send back.
+    NoDataExpectedResponse,
+    /// Reading from the bus failed.
+    I2CRead,
+    /// Writing to the bus failed.
+    I2CWrite,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::CommandParse => write!(f, "could not parse command"),
+            ErrorKind::ResponseParse => write!(f, "could not parse response"),
+            ErrorKind::MalformedResponse => {
+                write!(f, "response is not a valid nul-terminated UTF-8 string")
+            }
+            ErrorKind::DeviceErrorResponse => write!(f, "response was error"),
+            ErrorKind::PendingResponse => write!(f, "the device has not finished processing"),
+            ErrorKind::NoDataExpectedResponse => write!(f, "the device has no data to respond"),
+            ErrorKind::I2CRead => write!(f, "unsuccessful device read"),
+            ErrorKind::I2CWrite => write!(f, "unsuccessful device write"),
+        }
+    }
+}
+
+/// Error yielded by the response parsers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EzoError {
+    kind: ErrorKind,
+}
+
+impl EzoError {
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl From<ErrorKind> for EzoError {
+    fn from(kind: ErrorKind) -> EzoError {
+        EzoError { kind }
+    }
+}
+
+impl fmt::Display for EzoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.kind, f)
+    }
+}
+
+/// Replaces the error of a `Result` with an `EzoError` of the given kind,
+/// as `failure::ResultExt::context` does with `std`.
+pub(crate) trait ResultExt<T> {
+    fn context(self, kind: ErrorKind) -> Result<T, EzoError>;
+}
+
+impl<T, E> ResultExt<T> for Result<T, E> {
+    fn context(self, kind: ErrorKind) -> Result<T, EzoError> {
+        self.map_err(|_| kind.into())
+    }
+}
+
+/// Reason for the chip's last restart, as reported by `Status` or by the
+/// `*RS` restart notification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RestartReason {
+    PoweredOff,
+    SoftwareReset,
+    BrownOut,
+    Watchdog,
+    Unknown,
+}
+
+impl RestartReason {
+    /// Parses the restart code, e.g. `B` for a brown-out.
+    pub fn parse(code: &str) -> Result<RestartReason, EzoError> {
+        match code {
+            "P" => Ok(RestartReason::PoweredOff),
+            "S" => Ok(RestartReason::SoftwareReset),
+            "B" => Ok(RestartReason::BrownOut),
+            "W" => Ok(RestartReason::Watchdog),
+            "U" => Ok(RestartReason::Unknown),
+            _ => Err(ErrorKind::ResponseParse.into()),
+        }
+    }
+}
+
+impl fmt::Display for RestartReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RestartReason::PoweredOff => write!(f, "powered-off"),
+            RestartReason::SoftwareReset => write!(f, "software-reset"),
+            RestartReason::BrownOut => write!(f, "brown-out"),
+            RestartReason::Watchdog => write!(f, "watchdog"),
+            RestartReason::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Response code sent by the chip before its response string.
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResponseCode {
+    NoDataExpected,
+    Pending,
+    DeviceError,
+    Success,
+    UnknownError,
+}
+
+/// Decodes the first byte read back from the chip.
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+pub fn response_code(code: u8) -> ResponseCode {
+    match code {
+        255 => ResponseCode::NoDataExpected,
+        254 => ResponseCode::Pending,
+        2 => ResponseCode::DeviceError,
+        1 => ResponseCode::Success,
+        _ => ResponseCode::UnknownError,
+    }
+}
+
+/// The response string in `data`, up to its nul terminator.
+#[cfg(any(feature = "embedded-hal", feature = "async"))]
+pub fn string_from_response_data(data: &[u8]) -> Result<String, EzoError> {
+    let end = data.iter().position(|&x| x == 0).unwrap_or(data.len());
+    let text = core::str::from_utf8(&data[..end]).context(ErrorKind::MalformedResponse)?;
+    Ok(String::from(text))
+}
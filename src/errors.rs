@@ -0,0 +1,438 @@
+//! Errors specific to the EC EZO chip, complementing those from `ezo_common`.
+//!
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use failure::Fail;
+
+use super::command::OutputParameter;
+use super::response::ProbeType;
+use super::{ErrorKind, EzoError};
+
+pub use super::response::InvalidField;
+
+/// Response code returned by the chip in place of a successful response.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeviceError {
+    pub code: u8,
+}
+
+impl DeviceError {
+    /// The chip is still processing the command (code 254).
+    pub fn is_pending(&self) -> bool {
+        self.code == 254
+    }
+
+    /// The chip could not carry out the command (code 2).
+    pub fn is_failure(&self) -> bool {
+        self.code == 2
+    }
+
+    /// The chip has no data to send back (code 255).
+    pub fn is_no_data(&self) -> bool {
+        self.code == 255
+    }
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "device returned response code {}", self.code)
+    }
+}
+
+impl Fail for DeviceError {}
+
+/// Returns the response code carried by `error`, when it was caused by the
+/// chip replying with something other than success.
+pub fn device_error(error: &EzoError) -> Option<DeviceError> {
+    find_cause::<DeviceError>(error).cloned()
+}
+
+/// A command argument outside the range the chip accepts.
+///
+/// `ErrorKind` is defined in `ezo_common`, so there is no
+/// `ErrorKind::ValueOutOfRange` to match on: these errors are of kind
+/// `ErrorKind::CommandParse`, and are told apart from other parse errors
+/// with `is_value_out_of_range` or `value_out_of_range`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ValueOutOfRange {
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ValueOutOfRange {
+    /// Checks that `min <= value <= max`. Otherwise, fails with
+    /// `ErrorKind::CommandParse`, caused by a `ValueOutOfRange`.
+    pub(crate) fn check(value: f64, min: f64, max: f64) -> Result<(), EzoError> {
+        if (min..=max).contains(&value) {
+            Ok(())
+        } else {
+            Err(ValueOutOfRange { value, min, max }
+                .context(ErrorKind::CommandParse)
+                .into())
+        }
+    }
+}
+
+impl fmt::Display for ValueOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "value {} is outside the accepted range [{}, {}]",
+            self.value, self.min, self.max
+        )
+    }
+}
+
+impl Fail for ValueOutOfRange {}
+
+/// Returns the out-of-range argument carried by `error`, when it was caused
+/// by a command value the chip doesn't accept.
+pub fn value_out_of_range(error: &EzoError) -> Option<ValueOutOfRange> {
+    find_cause::<ValueOutOfRange>(error).cloned()
+}
+
+/// Whether `error` was caused by a command value the chip doesn't accept.
+pub fn is_value_out_of_range(error: &EzoError) -> bool {
+    find_cause::<ValueOutOfRange>(error).is_some()
+}
+
+/// The chip didn't answer again within the given time, e.g. after a reset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeviceTimeout {
+    pub timeout: Duration,
+}
+
+impl fmt::Display for DeviceTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "device did not respond within {} ms",
+            self.timeout.as_millis()
+        )
+    }
+}
+
+impl Fail for DeviceTimeout {}
+
+/// A response that was cut short, e.g. by a glitch on the bus. Retrying the
+/// command usually yields the whole response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruncatedResponse {
+    /// ASCII text received before the response broke off.
+    pub partial: String,
+}
+
+impl fmt::Display for TruncatedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "response truncated after {:?}", self.partial)
+    }
+}
+
+impl Fail for TruncatedResponse {}
+
+/// Returns the partial response carried by `error`, when it was caused by a
+/// truncated response.
+pub fn truncated_response(error: &EzoError) -> Option<TruncatedResponse> {
+    find_cause::<TruncatedResponse>(error).cloned()
+}
+
+/// Returns the field that failed to parse, when `error` comes from
+/// `ProbeReading::checked_parse`.
+pub fn invalid_field(error: &EzoError) -> Option<InvalidField> {
+    find_cause::<InvalidField>(error).cloned()
+}
+
+/// A command from a sequence failed, stopping the rest of the sequence.
+#[derive(Debug)]
+pub struct SequenceError {
+    /// Zero-based position of the failed command in the sequence.
+    pub index: usize,
+    /// Command string of the failed command.
+    pub command: String,
+    /// Error returned by the failed command.
+    pub error: EzoError,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "command {} ('{}') failed: {}",
+            self.index, self.command, self.error
+        )
+    }
+}
+
+impl Fail for SequenceError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        Some(&self.error)
+    }
+}
+
+/// An output parameter that the probe type can't produce. The chip accepts
+/// it, but reports a meaningless value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedOutput {
+    pub parameter: OutputParameter,
+    pub probe: ProbeType,
+}
+
+impl fmt::Display for UnsupportedOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a K {} probe can't produce the {} output parameter",
+            self.probe,
+            self.parameter.as_str()
+        )
+    }
+}
+
+impl Fail for UnsupportedOutput {}
+
+/// Returns the parameter and probe type, when `error` was caused by an
+/// output parameter the probe can't produce.
+pub fn unsupported_output(error: &EzoError) -> Option<UnsupportedOutput> {
+    find_cause::<UnsupportedOutput>(error).cloned()
+}
+
+/// A command was refused because the chip's protocol lock is on, as the chip
+/// would silently ignore it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtocolLocked {
+    /// Command string of the refused command.
+    pub command: String,
+}
+
+impl fmt::Display for ProtocolLocked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "protocol lock is on, so '{}' would be ignored; disable it with 'PLOCK,0' first",
+            self.command
+        )
+    }
+}
+
+impl Fail for ProtocolLocked {}
+
+/// Returns the refused command, when `error` was caused by the protocol
+/// lock.
+pub fn protocol_locked(error: &EzoError) -> Option<ProtocolLocked> {
+    find_cause::<ProtocolLocked>(error).cloned()
+}
+
+/// A setting read back from the chip doesn't match the one just applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettingMismatch {
+    /// Name of the setting, e.g. `"probe type"`.
+    pub setting: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for SettingMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} was set to {}, but the device reports {}",
+            self.setting, self.expected, self.actual
+        )
+    }
+}
+
+impl Fail for SettingMismatch {}
+
+/// Returns the setting that didn't take, when `error` was caused by a
+/// verification failure.
+pub fn setting_mismatch(error: &EzoError) -> Option<SettingMismatch> {
+    find_cause::<SettingMismatch>(error).cloned()
+}
+
+/// Names the command whose exchange with the chip failed.
+#[derive(Debug)]
+pub struct CommandFailed {
+    /// Command string sent to the chip.
+    pub command: String,
+    /// Error returned while running the command.
+    pub error: EzoError,
+}
+
+impl CommandFailed {
+    /// Wraps `error` so that it names `command`, keeping its `ErrorKind`.
+    pub(crate) fn wrap(command: &str, error: EzoError) -> EzoError {
+        let kind = error.kind();
+        CommandFailed {
+            command: command.to_string(),
+            error,
+        }
+        .context(kind)
+        .into()
+    }
+}
+
+impl fmt::Display for CommandFailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} to '{}'", self.error, self.command)
+    }
+}
+
+impl Fail for CommandFailed {
+    fn cause(&self) -> Option<&dyn Fail> {
+        Some(&self.error)
+    }
+}
+
+/// Returns the command string named by `error`, when it was returned while
+/// running a command.
+pub fn failed_command(error: &EzoError) -> Option<String> {
+    find_cause::<CommandFailed>(error).map(|failed| failed.command.clone())
+}
+
+/// An `EzoError` that implements `std::error::Error`, for callers that
+/// handle errors as `Box<dyn std::error::Error>`, or with crates built on
+/// it.
+///
+/// Its message names the failed command and the underlying cause, when
+/// known, e.g. `the device responded with an error to 'T,?': ...`.
+#[derive(Debug)]
+pub struct EcError(EzoError);
+
+impl EcError {
+    /// Kind of the wrapped error.
+    pub fn kind(&self) -> ErrorKind {
+        self.0.kind()
+    }
+
+    /// The wrapped error.
+    pub fn get_ref(&self) -> &EzoError {
+        &self.0
+    }
+
+    /// Unwraps the error.
+    pub fn into_inner(self) -> EzoError {
+        self.0
+    }
+}
+
+impl From<EzoError> for EcError {
+    fn from(error: EzoError) -> EcError {
+        EcError(error)
+    }
+}
+
+impl From<ErrorKind> for EcError {
+    fn from(kind: ErrorKind) -> EcError {
+        EcError(kind.into())
+    }
+}
+
+impl From<EcError> for EzoError {
+    fn from(error: EcError) -> EzoError {
+        error.0
+    }
+}
+
+impl fmt::Display for EcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        if let Some(command) = failed_command(&self.0) {
+            write!(f, " to '{}'", command)?;
+        }
+        let error: &dyn Fail = &self.0;
+        let root = error.find_root_cause();
+        if root.downcast_ref::<EzoError>().is_none() {
+            write!(f, ": {}", root)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for EcError {}
+
+/// Keeps the message of the error. Malformed commands map to
+/// `InvalidInput`, unparseable responses to `InvalidData`, and I2C and other
+/// failures to `Other`.
+impl From<EcError> for io::Error {
+    fn from(error: EcError) -> io::Error {
+        let kind = match error.kind() {
+            ErrorKind::UnreadableCommand
+            | ErrorKind::UnwritableCommand
+            | ErrorKind::CommandParse => io::ErrorKind::InvalidInput,
+            ErrorKind::ResponseParse | ErrorKind::MalformedResponse => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// First cause of `error` of type `T`, anywhere in its chain.
+fn find_cause<T: Fail>(error: &EzoError) -> Option<&T> {
+    let error: &dyn Fail = error;
+    error
+        .iter_causes()
+        .find_map(|cause| cause.downcast_ref::<T>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_ec_error_as_std_error() {
+        let error: EzoError = DeviceError { code: 2 }
+            .context(ErrorKind::DeviceErrorResponse)
+            .into();
+        let error = CommandFailed::wrap("T,?", error);
+
+        let boxed: Box<dyn Error + Send + Sync> = Box::new(EcError::from(error));
+        assert_eq!(
+            boxed.to_string(),
+            format!(
+                "{} to 'T,?': device returned response code 2",
+                ErrorKind::DeviceErrorResponse
+            )
+        );
+        let error = boxed.downcast::<EcError>().unwrap();
+        assert_eq!(error.kind(), ErrorKind::DeviceErrorResponse);
+        assert_eq!(failed_command(error.get_ref()).unwrap(), "T,?");
+    }
+
+    #[test]
+    fn converts_ec_error_into_io_error() {
+        let error = CommandFailed::wrap("R", ErrorKind::I2CRead.into());
+        let error = io::Error::from(EcError::from(error));
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        assert_eq!(error.to_string(), format!("{} to 'R'", ErrorKind::I2CRead));
+        let inner = error.get_ref().unwrap().downcast_ref::<EcError>().unwrap();
+        assert_eq!(inner.kind(), ErrorKind::I2CRead);
+
+        let error = io::Error::from(EcError::from(ErrorKind::ResponseParse));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = io::Error::from(EcError::from(ErrorKind::CommandParse));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn tells_value_out_of_range_from_other_parse_errors() {
+        let error = ValueOutOfRange::check(1.5, 0.01, 1.0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::CommandParse);
+        assert!(is_value_out_of_range(&error));
+        assert!(ValueOutOfRange::check(0.5, 0.01, 1.0).is_ok());
+
+        let error = EzoError::from(ErrorKind::CommandParse);
+        assert!(!is_value_out_of_range(&error));
+        assert_eq!(value_out_of_range(&error), None);
+    }
+
+    #[test]
+    fn plain_ec_error_displays_its_kind() {
+        let error = EcError::from(ErrorKind::ResponseParse);
+        assert_eq!(error.to_string(), ErrorKind::ResponseParse.to_string());
+        assert_eq!(EzoError::from(error).kind(), ErrorKind::ResponseParse);
+    }
+}
@@ -0,0 +1,670 @@
+//! Smoothing of noisy readings from the EC EZO chip, detection of sensors
+//! that stopped updating, and aggregation of readings over time buckets.
+//!
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use super::response::ProbeReading;
+
+/// Smallest `alpha` of an exponential moving average. Each reading still
+/// moves the average, which spans roughly the last thousand readings.
+const MIN_EXPONENTIAL_ALPHA: f64 = 0.001;
+
+/// Whether a reading pushed to a `ReadingSmoother` was kept.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SampleStatus {
+    Accepted,
+    /// The reading was an outlier and was discarded.
+    Rejected,
+}
+
+/// How a `ReadingSmoother` averages readings.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SmoothingStrategy {
+    /// Simple moving average over the last `window` readings.
+    MovingAverage { window: usize },
+    /// Exponential moving average, where each reading moves the average by
+    /// `alpha` of its difference from it. A larger `alpha` follows real
+    /// changes faster, and suppresses less noise.
+    ///
+    /// `alpha` lies in `0.001..=1.0`; `1.0` follows each reading as is.
+    Exponential { alpha: f64 },
+}
+
+/// Average of readings, computed per parameter: a moving average over the
+/// last `window` readings, or an exponential moving average.
+///
+/// Only readings with the same number of values are averaged together. A
+/// reading of a different arity, e.g. after the output string changes,
+/// starts a new window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingSmoother {
+    strategy: SmoothingStrategy,
+    threshold: Option<f64>,
+    readings: VecDeque<ProbeReading>,
+    ema: Option<ProbeReading>,
+}
+
+impl ReadingSmoother {
+    /// Creates a smoother averaging over `window` readings. A `window` of
+    /// zero is treated as one.
+    pub fn new(window: usize) -> ReadingSmoother {
+        ReadingSmoother::with_strategy(SmoothingStrategy::MovingAverage { window })
+    }
+
+    /// Creates a smoother with an exponential moving average. `alpha` is
+    /// clamped to `0.001..=1.0`, and a NaN `alpha` is treated as `1.0`.
+    pub fn exponential(alpha: f64) -> ReadingSmoother {
+        ReadingSmoother::with_strategy(SmoothingStrategy::Exponential { alpha })
+    }
+
+    /// Creates a smoother averaging with `strategy`, clamping its parameters
+    /// as `new` and `exponential` do.
+    pub fn with_strategy(strategy: SmoothingStrategy) -> ReadingSmoother {
+        let strategy = match strategy {
+            SmoothingStrategy::MovingAverage { window } => SmoothingStrategy::MovingAverage {
+                window: window.max(1),
+            },
+            SmoothingStrategy::Exponential { alpha } if alpha.is_nan() => {
+                SmoothingStrategy::Exponential { alpha: 1.0 }
+            }
+            SmoothingStrategy::Exponential { alpha } => SmoothingStrategy::Exponential {
+                alpha: alpha.clamp(MIN_EXPONENTIAL_ALPHA, 1.0),
+            },
+        };
+        ReadingSmoother {
+            strategy,
+            threshold: None,
+            readings: VecDeque::with_capacity(window_of(strategy)),
+            ema: None,
+        }
+    }
+
+    /// Creates a moving-average smoother that also rejects readings deviating
+    /// more than `deviations` standard deviations from the mean of a full
+    /// window.
+    pub fn with_outlier_rejection(window: usize, deviations: f64) -> ReadingSmoother {
+        ReadingSmoother {
+            threshold: Some(deviations),
+            ..ReadingSmoother::new(window)
+        }
+    }
+
+    /// How the smoother averages readings.
+    pub fn strategy(&self) -> SmoothingStrategy {
+        self.strategy
+    }
+
+    /// Number of readings averaged once the window is full. One for an
+    /// exponential moving average, which averages from the first reading.
+    pub fn window(&self) -> usize {
+        window_of(self.strategy)
+    }
+
+    /// Adds a reading, dropping the oldest one once the window is full.
+    ///
+    /// With outlier rejection enabled, a reading that deviates too much from
+    /// a full window is discarded instead, leaving the window unchanged.
+    pub fn push(&mut self, reading: ProbeReading) -> SampleStatus {
+        if let SmoothingStrategy::Exponential { alpha } = self.strategy {
+            self.ema = match self.ema {
+                Some(ema) if ema.len() == reading.len() => {
+                    let values: Vec<f64> = ema
+                        .iter()
+                        .zip(reading.iter())
+                        .map(|(average, value)| average + alpha * (value - average))
+                        .collect();
+                    Some(reading_from_values(&values))
+                }
+                _ => Some(reading),
+            };
+            return SampleStatus::Accepted;
+        }
+        if let Some(last) = self.readings.back() {
+            if last.len() != reading.len() {
+                self.readings.clear();
+            }
+        }
+        if self.is_outlier(&reading) {
+            return SampleStatus::Rejected;
+        }
+        if self.readings.len() == self.window() {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(reading);
+        SampleStatus::Accepted
+    }
+
+    /// Returns `true` when any value of `reading` lies further from the
+    /// window mean than the threshold allows. Windows that are not full, or
+    /// whose values don't vary, reject nothing.
+    fn is_outlier(&self, reading: &ProbeReading) -> bool {
+        let threshold = match self.threshold {
+            Some(threshold) if self.readings.len() == self.window() => threshold,
+            _ => return false,
+        };
+        let means = match self.average() {
+            Some(means) => means,
+            None => return false,
+        };
+        let count = self.readings.len() as f64;
+        let outlier = means
+            .iter()
+            .zip(reading.iter())
+            .enumerate()
+            .any(|(index, (mean, value))| {
+                let variance = self
+                    .readings
+                    .iter()
+                    .filter_map(|r| r.get(index))
+                    .map(|v| (v - mean) * (v - mean))
+                    .sum::<f64>()
+                    / count;
+                // Compared squared, as `f64::sqrt` needs `std`.
+                variance > 0.0 && (value - mean) * (value - mean) > threshold * threshold * variance
+            });
+        outlier
+    }
+
+    /// Per-parameter mean of the readings in the window, or `None` until
+    /// the window is full. With an exponential moving average, `None` until
+    /// the first reading.
+    pub fn average(&self) -> Option<ProbeReading> {
+        if let SmoothingStrategy::Exponential { .. } = self.strategy {
+            return self.ema;
+        }
+        if self.readings.len() < self.window() {
+            return None;
+        }
+        let mut sums: Vec<f64> = self.readings.front()?.iter().map(|_| 0.0).collect();
+        for reading in &self.readings {
+            for (sum, value) in sums.iter_mut().zip(reading.iter()) {
+                *sum += value;
+            }
+        }
+        let count = self.readings.len() as f64;
+        let means: Vec<f64> = sums.iter().map(|sum| sum / count).collect();
+        Some(reading_from_values(&means))
+    }
+
+    /// Drops every reading in the window.
+    pub fn clear(&mut self) {
+        self.readings.clear();
+        self.ema = None;
+    }
+}
+
+/// Number of readings `strategy` averages once its window is full.
+fn window_of(strategy: SmoothingStrategy) -> usize {
+    match strategy {
+        SmoothingStrategy::MovingAverage { window } => window,
+        SmoothingStrategy::Exponential { .. } => 1,
+    }
+}
+
+/// Whether the readings pushed to a `StallDetector` still change.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StallStatus {
+    Changing,
+    /// Every reading in a full window was the same, within the epsilon.
+    Stalled,
+}
+
+/// Flags a sensor that keeps reporting the same reading, as a hung chip
+/// does, which would otherwise pass for stable water.
+///
+/// Readings of a different arity start a new window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StallDetector {
+    window: usize,
+    epsilon: f64,
+    readings: VecDeque<ProbeReading>,
+}
+
+impl StallDetector {
+    /// Creates a detector that reports a stall once `window` readings in a
+    /// row differ by no more than `epsilon`. A `window` below two is treated
+    /// as two.
+    pub fn new(window: usize, epsilon: f64) -> StallDetector {
+        let window = window.max(2);
+        StallDetector {
+            window,
+            epsilon,
+            readings: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Number of unchanged readings that make a stall.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Adds a reading, dropping the oldest one once the window is full, and
+    /// returns whether the sensor looks stalled.
+    pub fn push(&mut self, reading: ProbeReading) -> StallStatus {
+        if let Some(last) = self.readings.back() {
+            if last.len() != reading.len() {
+                self.readings.clear();
+            }
+        }
+        if self.readings.len() == self.window {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(reading);
+        self.status()
+    }
+
+    /// `Stalled` when the window is full and every reading in it matches the
+    /// first one, within the epsilon.
+    pub fn status(&self) -> StallStatus {
+        let first = match self.readings.front() {
+            Some(first) if self.readings.len() == self.window => first,
+            _ => return StallStatus::Changing,
+        };
+        let epsilon = self.epsilon;
+        let unchanged = self.readings.iter().skip(1).all(|reading| {
+            first
+                .iter()
+                .zip(reading.iter())
+                .all(|(a, b)| a - b <= epsilon && b - a <= epsilon)
+        });
+        if unchanged {
+            StallStatus::Stalled
+        } else {
+            StallStatus::Changing
+        }
+    }
+
+    /// Drops every reading in the window.
+    pub fn clear(&mut self) {
+        self.readings.clear();
+    }
+}
+
+/// Statistics of the readings that fell in one bucket of a `ReadingBucket`,
+/// per parameter, in output-string order.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BucketStats {
+    /// Start of the bucket, on the same clock as the pushed times.
+    pub start: Duration,
+    /// Number of readings aggregated.
+    pub count: usize,
+    /// Number of readings left out, as empty or of a different arity than
+    /// the first one in the bucket.
+    pub ignored: usize,
+    pub min: ProbeReading,
+    pub max: ProbeReading,
+    pub mean: ProbeReading,
+}
+
+/// Aggregates readings into min, max and mean per parameter over buckets
+/// of wall-clock time, e.g. to downsample before storing them in a
+/// time-series database.
+///
+/// Buckets are aligned on multiples of the interval. Times are given by the
+/// caller, as the time elapsed since any fixed epoch, e.g. `UNIX_EPOCH`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingBucket {
+    interval: Duration,
+    start: Option<Duration>,
+    count: usize,
+    ignored: usize,
+    min: Vec<f64>,
+    max: Vec<f64>,
+    sums: Vec<f64>,
+}
+
+impl ReadingBucket {
+    /// Creates an aggregator with buckets `interval` long. An `interval` of
+    /// zero is treated as one nanosecond.
+    pub fn new(interval: Duration) -> ReadingBucket {
+        ReadingBucket {
+            interval: interval.max(Duration::from_nanos(1)),
+            start: None,
+            count: 0,
+            ignored: 0,
+            min: Vec::new(),
+            max: Vec::new(),
+            sums: Vec::new(),
+        }
+    }
+
+    /// Length of each bucket.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Adds a reading taken at `at`. When `at` lies past the end of the open
+    /// bucket, that bucket is closed and its statistics are returned, and
+    /// the reading opens the bucket it falls in.
+    ///
+    /// Readings taken before the open bucket, e.g. after the clock stepped
+    /// back, are aggregated into it.
+    pub fn push(&mut self, at: Duration, reading: ProbeReading) -> Option<BucketStats> {
+        let closed = match self.start {
+            Some(start) if at >= start + self.interval => self.flush(),
+            _ => None,
+        };
+        if self.start.is_none() {
+            let offset = at.as_nanos() % self.interval.as_nanos();
+            self.start = Some(at - Duration::from_nanos(offset as u64));
+        }
+        if reading.is_empty() || (self.count > 0 && reading.len() != self.sums.len()) {
+            self.ignored += 1;
+        } else if self.count == 0 {
+            self.min = reading.iter().collect();
+            self.max = self.min.clone();
+            self.sums = self.min.clone();
+            self.count = 1;
+        } else {
+            for (index, value) in reading.iter().enumerate() {
+                self.min[index] = self.min[index].min(value);
+                self.max[index] = self.max[index].max(value);
+                self.sums[index] += value;
+            }
+            self.count += 1;
+        }
+        closed
+    }
+
+    /// Closes the open bucket, e.g. when shutting down, and returns its
+    /// statistics, or `None` when it aggregated no readings.
+    pub fn flush(&mut self) -> Option<BucketStats> {
+        let start = self.start.take()?;
+        let count = self.count;
+        let ignored = self.ignored;
+        self.count = 0;
+        self.ignored = 0;
+        if count == 0 {
+            return None;
+        }
+        let means: Vec<f64> = self.sums.iter().map(|sum| sum / count as f64).collect();
+        Some(BucketStats {
+            start,
+            count,
+            ignored,
+            min: reading_from_values(&self.min),
+            max: reading_from_values(&self.max),
+            mean: reading_from_values(&means),
+        })
+    }
+}
+
+/// Builds the reading holding `values`, in output-string order.
+fn reading_from_values(values: &[f64]) -> ProbeReading {
+    match *values {
+        [a] => ProbeReading::OneParameter(a),
+        [a, b] => ProbeReading::TwoParameters(a, b),
+        [a, b, c] => ProbeReading::ThreeParameters(a, b, c),
+        [a, b, c, d] => ProbeReading::FourParameters(a, b, c, d),
+        _ => ProbeReading::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_readings_once_window_is_full() {
+        let mut smoother = ReadingSmoother::new(3);
+        smoother.push(ProbeReading::OneParameter(10.0));
+        smoother.push(ProbeReading::OneParameter(12.0));
+        assert_eq!(smoother.average(), None);
+
+        smoother.push(ProbeReading::OneParameter(14.0));
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(12.0)));
+
+        smoother.push(ProbeReading::OneParameter(19.0));
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(15.0)));
+    }
+
+    #[test]
+    fn averages_each_parameter_separately() {
+        let mut smoother = ReadingSmoother::new(2);
+        smoother.push(ProbeReading::TwoParameters(10.0, 5.0));
+        smoother.push(ProbeReading::TwoParameters(20.0, 7.0));
+        assert_eq!(
+            smoother.average(),
+            Some(ProbeReading::TwoParameters(15.0, 6.0))
+        );
+    }
+
+    #[test]
+    fn reading_of_different_arity_restarts_window() {
+        let mut smoother = ReadingSmoother::new(2);
+        smoother.push(ProbeReading::OneParameter(10.0));
+        smoother.push(ProbeReading::OneParameter(20.0));
+        smoother.push(ProbeReading::TwoParameters(1.0, 2.0));
+        assert_eq!(smoother.average(), None);
+
+        smoother.push(ProbeReading::TwoParameters(3.0, 4.0));
+        assert_eq!(
+            smoother.average(),
+            Some(ProbeReading::TwoParameters(2.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn rejects_outliers_from_full_window() {
+        let mut smoother = ReadingSmoother::with_outlier_rejection(4, 3.0);
+        for value in [12.4, 12.6, 12.5, 12.5].iter() {
+            assert_eq!(
+                smoother.push(ProbeReading::OneParameter(*value)),
+                SampleStatus::Accepted
+            );
+        }
+        assert_eq!(
+            smoother.push(ProbeReading::OneParameter(0.0)),
+            SampleStatus::Rejected
+        );
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(12.5)));
+
+        assert_eq!(
+            smoother.push(ProbeReading::OneParameter(12.6)),
+            SampleStatus::Accepted
+        );
+    }
+
+    #[test]
+    fn accepts_everything_without_outlier_rejection() {
+        let mut smoother = ReadingSmoother::new(2);
+        smoother.push(ProbeReading::OneParameter(12.5));
+        smoother.push(ProbeReading::OneParameter(12.7));
+        assert_eq!(
+            smoother.push(ProbeReading::OneParameter(0.0)),
+            SampleStatus::Accepted
+        );
+    }
+
+    #[test]
+    fn exponential_average_converges_to_step_change() {
+        let mut smoother = ReadingSmoother::exponential(0.5);
+        assert_eq!(smoother.average(), None);
+        smoother.push(ProbeReading::OneParameter(0.0));
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(0.0)));
+
+        // Each reading halves the distance left to the new level.
+        let mut expected = 0.0;
+        for _ in 0..10 {
+            smoother.push(ProbeReading::OneParameter(100.0));
+            expected += 0.5 * (100.0 - expected);
+            assert_eq!(
+                smoother.average(),
+                Some(ProbeReading::OneParameter(expected))
+            );
+        }
+        match smoother.average() {
+            Some(ProbeReading::OneParameter(value)) => assert!(100.0 - value < 0.1),
+            other => panic!("unexpected average {:?}", other),
+        }
+    }
+
+    #[test]
+    fn exponential_average_follows_each_parameter() {
+        let mut smoother =
+            ReadingSmoother::with_strategy(SmoothingStrategy::Exponential { alpha: 0.25 });
+        smoother.push(ProbeReading::TwoParameters(10.0, 5.0));
+        smoother.push(ProbeReading::TwoParameters(18.0, 1.0));
+        assert_eq!(
+            smoother.average(),
+            Some(ProbeReading::TwoParameters(12.0, 4.0))
+        );
+
+        smoother.push(ProbeReading::OneParameter(3.0));
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(3.0)));
+        smoother.clear();
+        assert_eq!(smoother.average(), None);
+    }
+
+    #[test]
+    fn clamps_exponential_alpha() {
+        let smoother = ReadingSmoother::exponential(1.5);
+        assert_eq!(
+            smoother.strategy(),
+            SmoothingStrategy::Exponential { alpha: 1.0 }
+        );
+        assert_eq!(smoother.window(), 1);
+
+        let smoother = ReadingSmoother::exponential(0.0);
+        assert_eq!(
+            smoother.strategy(),
+            SmoothingStrategy::Exponential { alpha: 0.001 }
+        );
+        let smoother =
+            ReadingSmoother::with_strategy(SmoothingStrategy::Exponential { alpha: -0.5 });
+        assert_eq!(
+            smoother.strategy(),
+            SmoothingStrategy::Exponential { alpha: 0.001 }
+        );
+    }
+
+    #[test]
+    fn nan_exponential_alpha_follows_each_reading() {
+        let mut smoother = ReadingSmoother::exponential(f64::NAN);
+        assert_eq!(
+            smoother.strategy(),
+            SmoothingStrategy::Exponential { alpha: 1.0 }
+        );
+        smoother.push(ProbeReading::OneParameter(10.0));
+        smoother.push(ProbeReading::OneParameter(12.0));
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(12.0)));
+    }
+
+    #[test]
+    fn smallest_exponential_alpha_still_moves_the_average() {
+        let mut smoother = ReadingSmoother::exponential(0.0);
+        smoother.push(ProbeReading::OneParameter(10.0));
+        smoother.push(ProbeReading::OneParameter(1010.0));
+        assert_eq!(smoother.average(), Some(ProbeReading::OneParameter(11.0)));
+    }
+
+    #[test]
+    fn detects_stuck_readings() {
+        let mut detector = StallDetector::new(3, 0.001);
+        assert_eq!(
+            detector.push(ProbeReading::TwoParameters(12.5, 6.7)),
+            StallStatus::Changing
+        );
+        assert_eq!(
+            detector.push(ProbeReading::TwoParameters(12.6, 6.7)),
+            StallStatus::Changing
+        );
+        assert_eq!(
+            detector.push(ProbeReading::TwoParameters(12.6, 6.7)),
+            StallStatus::Changing
+        );
+        assert_eq!(
+            detector.push(ProbeReading::TwoParameters(12.6005, 6.7)),
+            StallStatus::Stalled
+        );
+        assert_eq!(
+            detector.push(ProbeReading::TwoParameters(12.6, 6.8)),
+            StallStatus::Changing
+        );
+    }
+
+    #[test]
+    fn aggregates_readings_per_time_bucket() {
+        let mut bucket = ReadingBucket::new(Duration::from_secs(60));
+        let secs = Duration::from_secs;
+        assert_eq!(
+            bucket.push(secs(125), ProbeReading::TwoParameters(12.0, 6.0)),
+            None
+        );
+        assert_eq!(
+            bucket.push(secs(150), ProbeReading::TwoParameters(14.0, 7.0)),
+            None
+        );
+        assert_eq!(
+            bucket.push(secs(179), ProbeReading::TwoParameters(13.0, 8.0)),
+            None
+        );
+
+        let stats = bucket
+            .push(secs(180), ProbeReading::TwoParameters(20.0, 10.0))
+            .unwrap();
+        assert_eq!(
+            stats,
+            BucketStats {
+                start: secs(120),
+                count: 3,
+                ignored: 0,
+                min: ProbeReading::TwoParameters(12.0, 6.0),
+                max: ProbeReading::TwoParameters(14.0, 8.0),
+                mean: ProbeReading::TwoParameters(13.0, 7.0),
+            }
+        );
+
+        let stats = bucket
+            .push(secs(400), ProbeReading::OneParameter(1.0))
+            .unwrap();
+        assert_eq!(stats.start, secs(180));
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.mean, ProbeReading::TwoParameters(20.0, 10.0));
+
+        let stats = bucket.flush().unwrap();
+        assert_eq!(stats.start, secs(360));
+        assert_eq!(stats.mean, ProbeReading::OneParameter(1.0));
+        assert_eq!(bucket.flush(), None);
+    }
+
+    #[test]
+    fn bucket_ignores_readings_of_different_arity() {
+        let mut bucket = ReadingBucket::new(Duration::from_secs(10));
+        bucket.push(Duration::from_secs(0), ProbeReading::OneParameter(12.0));
+        bucket.push(
+            Duration::from_secs(1),
+            ProbeReading::TwoParameters(1.0, 2.0),
+        );
+        bucket.push(Duration::from_secs(2), ProbeReading::None);
+        bucket.push(Duration::from_secs(3), ProbeReading::OneParameter(16.0));
+
+        let stats = bucket.flush().unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.ignored, 2);
+        assert_eq!(stats.min, ProbeReading::OneParameter(12.0));
+        assert_eq!(stats.max, ProbeReading::OneParameter(16.0));
+        assert_eq!(stats.mean, ProbeReading::OneParameter(14.0));
+
+        bucket.push(Duration::from_secs(20), ProbeReading::None);
+        assert_eq!(bucket.flush(), None);
+    }
+
+    #[test]
+    fn reading_of_different_arity_restarts_stall_window() {
+        let mut detector = StallDetector::new(2, 0.0);
+        detector.push(ProbeReading::OneParameter(12.5));
+        assert_eq!(
+            detector.push(ProbeReading::OneParameter(12.5)),
+            StallStatus::Stalled
+        );
+        assert_eq!(
+            detector.push(ProbeReading::TwoParameters(12.5, 6.7)),
+            StallStatus::Changing
+        );
+        detector.clear();
+        assert_eq!(detector.status(), StallStatus::Changing);
+    }
+}
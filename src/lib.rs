@@ -1,16 +1,87 @@
 //! I2C Commands for EZO EC Chip, taken from their Datasheet.
 //! This chip is used for electrical conductivity measurement. It features
 //! calibration, sleep mode, scale, etc.
-extern crate failure;
+//!
+//! The response parsers only need `core` and `alloc`, and so do the command
+//! exchanges in `bus`, over `embedded-hal` and `embedded-hal-async` I2C
+//! buses. The commands, and everything else that talks to the chip, live
+//! behind the `std` feature, which is on by default.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "std")]
+extern crate core;
+#[cfg(feature = "embedded-hal")]
+extern crate embedded_hal;
+#[cfg(feature = "async")]
+extern crate embedded_hal_async;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate ezo_common;
+extern crate failure;
+#[cfg(feature = "std")]
 extern crate i2cdev;
+#[cfg(feature = "std")]
+extern crate libc;
+#[cfg(feature = "logging")]
+extern crate log;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+/// Emits a `log::debug!` record when the `logging` feature is enabled, and
+/// nothing otherwise.
+#[cfg(any(feature = "std", feature = "embedded-hal", feature = "async"))]
+macro_rules! trace_exchange {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "logging")]
+        log::debug!($($arg)*);
+    };
+}
+
+/// Command exchanges over `embedded-hal` and `embedded-hal-async` I2C buses.
+#[cfg(any(feature = "std", feature = "embedded-hal", feature = "async"))]
+pub mod bus;
 
 /// Issuable commands for the EZO EC Chip.
+#[cfg(feature = "std")]
 pub mod command;
 
+/// Stand-ins for the `ezo_common` types, for builds without `std`.
+#[cfg(not(feature = "std"))]
+mod core_compat;
+
 /// Parseable responses from the EZO EC Chip.
 pub mod response;
 
+/// Errors specific to the EZO EC Chip.
+#[cfg(feature = "std")]
+pub mod errors;
+
+/// Pluggable links to the EZO EC Chip.
+#[cfg(feature = "std")]
+pub mod transport;
+
+/// Mock I2C device for testing command round-trips without hardware.
+#[cfg(feature = "mock")]
+pub mod testing;
+
+/// Host-side conversions between conductivity and derived metrics.
+#[cfg(feature = "std")]
+pub mod convert;
+
+/// High-level handle on the EZO EC Chip.
+#[cfg(feature = "std")]
+pub mod sensor;
+
+/// Smoothing and time-bucket aggregation of readings.
+pub mod filter;
+
 // Re-export errors from ezo_common crate.
+#[cfg(not(feature = "std"))]
+pub use core_compat::{ErrorKind, EzoError};
+#[cfg(feature = "std")]
 pub use ezo_common::errors::{ErrorKind, EzoError};
@@ -1,20 +1,41 @@
 //! Parses I2C responses from the EC EZO Chip.
 //!
 //! Code modified from "Federico Mena Quintero <federico@gnome.org>"'s original.
-use std::fmt;
-use std::str::FromStr;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
 use super::{ErrorKind, EzoError};
 
+#[cfg(not(feature = "std"))]
+use super::core_compat::ResultExt;
+use failure::Fail;
+#[cfg(feature = "std")]
 use failure::ResultExt;
 
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, SecondsFormat, Utc};
+
+#[cfg(all(feature = "std", feature = "chrono"))]
+use std::io;
+
+#[cfg(not(feature = "std"))]
+pub use super::core_compat::RestartReason;
+#[cfg(feature = "std")]
 pub use ezo_common::response::{
     DeviceInfo, DeviceStatus, Exported, ExportedInfo, LedStatus, ProtocolLockStatus,
     ResponseStatus, RestartReason,
 };
 
 /// Calibration status of the EC EZO chip.
-#[derive(Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum CalibrationStatus {
     OnePoint,
     TwoPoint,
@@ -44,6 +65,21 @@ impl CalibrationStatus {
             Err(ErrorKind::ResponseParse.into())
         }
     }
+
+    /// Returns `true` when this status is more calibrated than `previous`,
+    /// e.g. after a calibration point registered.
+    pub fn advanced_from(&self, previous: &CalibrationStatus) -> bool {
+        self.points() > previous.points()
+    }
+
+    /// Number of calibration points the status stands for.
+    fn points(&self) -> u8 {
+        match *self {
+            CalibrationStatus::NotCalibrated => 0,
+            CalibrationStatus::OnePoint => 1,
+            CalibrationStatus::TwoPoint => 2,
+        }
+    }
 }
 
 impl fmt::Debug for CalibrationStatus {
@@ -66,7 +102,166 @@ impl fmt::Display for CalibrationStatus {
     }
 }
 
+impl FromStr for CalibrationStatus {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        CalibrationStatus::parse(s)
+    }
+}
+
+impl TryFrom<u8> for CalibrationStatus {
+    type Error = EzoError;
+
+    /// Status with `points` calibration points, from 0 to 2, for compact
+    /// storage. Other counts fail with `ErrorKind::ResponseParse`.
+    fn try_from(points: u8) -> Result<Self, EzoError> {
+        match points {
+            0 => Ok(CalibrationStatus::NotCalibrated),
+            1 => Ok(CalibrationStatus::OnePoint),
+            2 => Ok(CalibrationStatus::TwoPoint),
+            _ => Err(ErrorKind::ResponseParse.into()),
+        }
+    }
+}
+
+impl From<CalibrationStatus> for u8 {
+    fn from(status: CalibrationStatus) -> u8 {
+        status.points()
+    }
+}
+
+/// Calibration progress of the EC EZO chip, detailing which points are set.
+///
+/// The chip only reports how many points are calibrated, so the caller fills
+/// in which calibration commands it has issued.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CalibrationDetail {
+    pub status: CalibrationStatus,
+    pub dry: bool,
+    pub single: bool,
+    pub low: bool,
+    pub high: bool,
+}
+
+impl Default for CalibrationDetail {
+    /// Not calibrated, with no points set.
+    fn default() -> CalibrationDetail {
+        CalibrationDetail::with(CalibrationStatus::NotCalibrated)
+    }
+}
+
+impl CalibrationDetail {
+    /// Creates a detail for the given status, with no points marked as set.
+    pub fn with(status: CalibrationStatus) -> CalibrationDetail {
+        CalibrationDetail {
+            status,
+            dry: false,
+            single: false,
+            low: false,
+            high: false,
+        }
+    }
+
+    /// Marks whether the `CAL,DRY` point is set.
+    pub fn set_dry(&mut self, done: bool) {
+        self.dry = done;
+    }
+
+    /// Marks whether the single `CAL,n` point is set.
+    pub fn set_single(&mut self, done: bool) {
+        self.single = done;
+    }
+
+    /// Marks whether the `CAL,LOW,n` point is set.
+    pub fn set_low(&mut self, done: bool) {
+        self.low = done;
+    }
+
+    /// Marks whether the `CAL,HIGH,n` point is set.
+    pub fn set_high(&mut self, done: bool) {
+        self.high = done;
+    }
+
+    /// Updates the status reported by the chip, keeping the marked points.
+    pub fn set_status(&mut self, status: CalibrationStatus) {
+        self.status = status;
+    }
+
+    /// Number of points marked as set, the dry point included.
+    pub fn completed_points(&self) -> usize {
+        [self.dry, self.single, self.low, self.high]
+            .iter()
+            .filter(|&&done| done)
+            .count()
+    }
+
+    /// Returns `true` when the marked points account for the chip's status.
+    pub fn is_complete(&self) -> bool {
+        match self.status {
+            CalibrationStatus::TwoPoint => self.dry && self.low && self.high,
+            CalibrationStatus::OnePoint => self.dry && (self.single || self.low),
+            CalibrationStatus::NotCalibrated => false,
+        }
+    }
+}
+
+/// Temperature scales accepted when building a `CompensationValue`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TemperatureScale {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+/// Temperature in degrees Celsius, the scale the chip expects for
+/// compensation.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Celsius(pub f64);
+
+impl From<f64> for Celsius {
+    fn from(degrees: f64) -> Celsius {
+        Celsius(degrees)
+    }
+}
+
+impl From<Celsius> for f64 {
+    fn from(temperature: Celsius) -> f64 {
+        temperature.0
+    }
+}
+
+impl From<CompensationValue> for Celsius {
+    fn from(value: CompensationValue) -> Celsius {
+        Celsius(value.0)
+    }
+}
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.*} °C", 3, self.0)
+    }
+}
+
+/// Whether `a` and `b` differ by no more than `epsilon`.
+///
+/// Spelled out without `f64::abs`, which needs `std`.
+fn within_epsilon(a: f64, b: f64, epsilon: f64) -> bool {
+    a - b <= epsilon && b - a <= epsilon
+}
+
+/// Lowest compensation temperature the chip accepts, in °C.
+const MIN_COMPENSATION_TEMPERATURE: f64 = -5.0;
+
+/// Highest compensation temperature the chip accepts, in °C.
+const MAX_COMPENSATION_TEMPERATURE: f64 = 110.0;
+
 /// Current temperature value used for sensor-reading compensation.
+///
+/// The value is always stored in degrees Celsius, which is what the chip
+/// expects and reports. Being an `f64`, it can't implement `Eq` or `Hash`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Copy, Clone, PartialEq)]
 pub struct CompensationValue(pub f64);
 
@@ -82,6 +277,72 @@ impl CompensationValue {
             Err(ErrorKind::ResponseParse.into())
         }
     }
+
+    /// Creates a compensation value from a temperature in the given scale.
+    pub fn from_scale(value: f64, scale: TemperatureScale) -> CompensationValue {
+        let celsius = match scale {
+            TemperatureScale::Celsius => value,
+            TemperatureScale::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            TemperatureScale::Kelvin => value - 273.15,
+        };
+        CompensationValue(celsius)
+    }
+
+    /// Creates a compensation value from a temperature in degrees Fahrenheit.
+    pub fn from_fahrenheit(value: f64) -> CompensationValue {
+        CompensationValue::from_scale(value, TemperatureScale::Fahrenheit)
+    }
+
+    /// Creates a compensation value from a temperature in Kelvin.
+    pub fn from_kelvin(value: f64) -> CompensationValue {
+        CompensationValue::from_scale(value, TemperatureScale::Kelvin)
+    }
+
+    /// Returns the compensation temperature in the given scale.
+    pub fn as_scale(&self, scale: TemperatureScale) -> f64 {
+        match scale {
+            TemperatureScale::Celsius => self.0,
+            TemperatureScale::Fahrenheit => self.0 * 9.0 / 5.0 + 32.0,
+            TemperatureScale::Kelvin => self.0 + 273.15,
+        }
+    }
+
+    /// Returns the compensation temperature in degrees Fahrenheit.
+    pub fn as_fahrenheit(&self) -> f64 {
+        self.as_scale(TemperatureScale::Fahrenheit)
+    }
+
+    /// Returns the compensation temperature in Kelvin.
+    pub fn as_kelvin(&self) -> f64 {
+        self.as_scale(TemperatureScale::Kelvin)
+    }
+
+    /// Clamps the value to the compensation range the chip accepts, from
+    /// -5°C to 110°C.
+    pub fn clamp_to_sensor_range(self) -> CompensationValue {
+        CompensationValue(
+            self.0
+                .clamp(MIN_COMPENSATION_TEMPERATURE, MAX_COMPENSATION_TEMPERATURE),
+        )
+    }
+
+    /// Returns the value when it lies within the compensation range the chip
+    /// accepts, from -5°C to 110°C. Otherwise, fails with
+    /// `ErrorKind::CommandParse`, caused by a `ValueOutOfRange`.
+    #[cfg(feature = "std")]
+    pub fn check_sensor_range(self) -> Result<CompensationValue, EzoError> {
+        crate::errors::ValueOutOfRange::check(
+            self.0,
+            MIN_COMPENSATION_TEMPERATURE,
+            MAX_COMPENSATION_TEMPERATURE,
+        )?;
+        Ok(self)
+    }
+
+    /// Whether both temperatures differ by no more than `epsilon` degrees.
+    pub fn approx_eq(&self, other: &CompensationValue, epsilon: f64) -> bool {
+        within_epsilon(self.0, other.0, epsilon)
+    }
 }
 
 impl fmt::Debug for CompensationValue {
@@ -96,8 +357,60 @@ impl fmt::Display for CompensationValue {
     }
 }
 
-/// The probe-type of the conductivity sensor.
+impl FromStr for CompensationValue {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        CompensationValue::parse(s)
+    }
+}
+
+/// Current conversion factor from conductivity to total dissolved solids.
+///
+/// Being an `f64`, it can't implement `Eq` or `Hash`.
 #[derive(Copy, Clone, PartialEq)]
+pub struct TdsFactor(pub f64);
+
+impl TdsFactor {
+    /// Parses the result of the "TDS,?" command to get the device's
+    /// conductivity to total dissolved solids conversion factor.
+    pub fn parse(response: &str) -> Result<TdsFactor, EzoError> {
+        if response.starts_with("?TDS,") {
+            let rest = response.get(5..).unwrap();
+            let val = f64::from_str(rest).context(ErrorKind::ResponseParse)?;
+            Ok(TdsFactor(val))
+        } else {
+            Err(ErrorKind::ResponseParse.into())
+        }
+    }
+}
+
+impl fmt::Debug for TdsFactor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "?TDS,{:.*}", 2, self.0)
+    }
+}
+
+impl fmt::Display for TdsFactor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.*}", 2, self.0)
+    }
+}
+
+impl FromStr for TdsFactor {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        TdsFactor::parse(s)
+    }
+}
+
+/// The probe-type of the conductivity sensor.
+///
+/// Ordered by cell constant. The variants are declared in that order, which
+/// the derived `Ord` follows.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ProbeType {
     PointOne,
     One,
@@ -105,6 +418,45 @@ pub enum ProbeType {
 }
 
 impl ProbeType {
+    /// Cell constant, K, of the probe.
+    pub fn cell_constant(&self) -> f64 {
+        match *self {
+            ProbeType::PointOne => 0.1,
+            ProbeType::One => 1.0,
+            ProbeType::Ten => 10.0,
+        }
+    }
+
+    /// Lowest and highest conductivity, in µS/cm, the probe is specified to
+    /// measure.
+    pub fn measurement_range(&self) -> (f64, f64) {
+        match *self {
+            ProbeType::PointOne => (0.07, 50_000.0),
+            ProbeType::One => (5.0, 200_000.0),
+            ProbeType::Ten => (10.0, 1_000_000.0),
+        }
+    }
+
+    /// Whether the chip can derive specific gravity from this probe. A K 0.1
+    /// probe doesn't reach seawater conductivity, so the chip keeps reporting
+    /// `0.000` for it.
+    pub fn supports_specific_gravity(&self) -> bool {
+        *self != ProbeType::PointOne
+    }
+
+    /// Classifies a conductivity value, in µS/cm, against the probe's
+    /// measurement range. Values outside of it may be clamped by the chip.
+    pub fn classify(&self, ec_us_cm: f64) -> ReadingQuality {
+        let (min, max) = self.measurement_range();
+        if ec_us_cm < min {
+            ReadingQuality::BelowRange
+        } else if ec_us_cm > max {
+            ReadingQuality::AboveRange
+        } else {
+            ReadingQuality::Ok
+        }
+    }
+
     /// Parses the result of the "Cal,?" command to query the device's
     /// calibration status.  Returns ...
     pub fn parse(response: &str) -> Result<ProbeType, EzoError> {
@@ -129,6 +481,32 @@ impl ProbeType {
     }
 }
 
+/// Where a conductivity value falls relative to a probe's measurement range.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ReadingQuality {
+    Ok,
+    BelowRange,
+    AboveRange,
+}
+
+/// The standard `ProbeType` whose cell constant is nearest `k`.
+///
+/// Cell constants span decades, so nearness is measured by ratio: values
+/// snap to 0.1 below ~0.316, to 1.0 below ~3.16, and to 10.0 above. Values
+/// that aren't positive snap to `PointOne`.
+pub fn probe_type_from_cell_constant(k: f64) -> ProbeType {
+    // Compared squared against the geometric means of neighbouring presets,
+    // as `f64::sqrt` needs `std`.
+    let boundary = |low: ProbeType, high: ProbeType| low.cell_constant() * high.cell_constant();
+    if k.is_nan() || k <= 0.0 || k * k < boundary(ProbeType::PointOne, ProbeType::One) {
+        ProbeType::PointOne
+    } else if k * k < boundary(ProbeType::One, ProbeType::Ten) {
+        ProbeType::One
+    } else {
+        ProbeType::Ten
+    }
+}
+
 impl fmt::Debug for ProbeType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -149,13 +527,53 @@ impl fmt::Display for ProbeType {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+impl FromStr for ProbeType {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        ProbeType::parse(s)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub enum ParameterStatus {
     On,
+    #[default]
     Off,
 }
 
+impl ParameterStatus {
+    /// The opposite status.
+    pub fn toggle(self) -> ParameterStatus {
+        match self {
+            ParameterStatus::On => ParameterStatus::Off,
+            ParameterStatus::Off => ParameterStatus::On,
+        }
+    }
+}
+
+impl From<bool> for ParameterStatus {
+    fn from(enabled: bool) -> ParameterStatus {
+        if enabled {
+            ParameterStatus::On
+        } else {
+            ParameterStatus::Off
+        }
+    }
+}
+
+impl From<ParameterStatus> for bool {
+    fn from(status: ParameterStatus) -> bool {
+        status == ParameterStatus::On
+    }
+}
+
 /// Current configuration of which sensing metrics appear in the output string.
+///
+/// When deserialized, missing parameters default to disabled.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[derive(Copy, Clone, PartialEq)]
 pub struct OutputStringStatus {
     pub electric_conductivity: ParameterStatus,
@@ -174,6 +592,30 @@ impl OutputStringStatus {
         }
     }
 
+    /// Sets whether conductivity is enabled in the output string.
+    pub fn with_conductivity(mut self, enabled: bool) -> OutputStringStatus {
+        self.electric_conductivity = enabled.into();
+        self
+    }
+
+    /// Sets whether total dissolved solids are enabled in the output string.
+    pub fn with_tds(mut self, enabled: bool) -> OutputStringStatus {
+        self.total_dissolved_solids = enabled.into();
+        self
+    }
+
+    /// Sets whether salinity is enabled in the output string.
+    pub fn with_salinity(mut self, enabled: bool) -> OutputStringStatus {
+        self.salinity = enabled.into();
+        self
+    }
+
+    /// Sets whether specific gravity is enabled in the output string.
+    pub fn with_specific_gravity(mut self, enabled: bool) -> OutputStringStatus {
+        self.specific_gravity = enabled.into();
+        self
+    }
+
     pub fn parse(response: &str) -> Result<OutputStringStatus, EzoError> {
         if response.starts_with("?O,") {
             let rest = response.get(3..).unwrap();
@@ -181,7 +623,7 @@ impl OutputStringStatus {
 
             let mut _output = OutputStringStatus::new();
 
-            let _first = match split.next() {
+            match split.next() {
                 Some("EC") => _output.electric_conductivity = ParameterStatus::On,
 
                 Some("TDS") => _output.total_dissolved_solids = ParameterStatus::On,
@@ -195,7 +637,7 @@ impl OutputStringStatus {
                 _ => return Err(ErrorKind::ResponseParse.into()),
             };
 
-            let _second = match split.next() {
+            match split.next() {
                 Some("TDS") => _output.total_dissolved_solids = ParameterStatus::On,
 
                 Some("S") => _output.salinity = ParameterStatus::On,
@@ -207,7 +649,7 @@ impl OutputStringStatus {
                 _ => return Err(ErrorKind::ResponseParse.into()),
             };
 
-            let _third = match split.next() {
+            match split.next() {
                 Some("S") => _output.salinity = ParameterStatus::On,
 
                 Some("SG") => _output.specific_gravity = ParameterStatus::On,
@@ -217,7 +659,7 @@ impl OutputStringStatus {
                 _ => return Err(ErrorKind::ResponseParse.into()),
             };
 
-            let _fourth = match split.next() {
+            match split.next() {
                 Some("SG") => _output.specific_gravity = ParameterStatus::On,
 
                 None => (),
@@ -225,7 +667,7 @@ impl OutputStringStatus {
                 _ => return Err(ErrorKind::ResponseParse.into()),
             };
 
-            if let Some(_) = split.next() {
+            if split.next().is_some() {
                 return Err(ErrorKind::ResponseParse.into());
             };
 
@@ -235,40 +677,175 @@ impl OutputStringStatus {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut _out: Vec<&str> = Vec::new();
+    /// Number of parameters enabled in the output string.
+    pub fn enabled_count(&self) -> usize {
+        [
+            self.electric_conductivity,
+            self.total_dissolved_solids,
+            self.salinity,
+            self.specific_gravity,
+        ]
+        .iter()
+        .filter(|&&status| status == ParameterStatus::On)
+        .count()
+    }
+
+    /// Returns `true` when no parameters are enabled in the output string.
+    pub fn is_empty(&self) -> bool {
+        self.enabled_count() == 0
+    }
+
+    /// Constructors for the metrics enabled in the output string, in the
+    /// order the chip reports them.
+    fn enabled_metrics(&self) -> Vec<fn(f64) -> ProbeMetric> {
+        let mut _out: Vec<fn(f64) -> ProbeMetric> = Vec::new();
 
         if self.electric_conductivity == ParameterStatus::On {
-            _out.push("EC");
+            _out.push(ProbeMetric::ElectricConductivity);
         }
         if self.total_dissolved_solids == ParameterStatus::On {
-            _out.push("TDS");
+            _out.push(ProbeMetric::TotalDissolvedSolids);
         }
         if self.salinity == ParameterStatus::On {
-            _out.push("S");
+            _out.push(ProbeMetric::Salinity);
         }
         if self.specific_gravity == ParameterStatus::On {
-            _out.push("SG");
-        }
-        match _out.len() {
-            1...4 => _out.join(","),
-            0 | _ => "No output".to_string(),
+            _out.push(ProbeMetric::SpecificGravity);
         }
+        _out
+    }
+
+    /// Labels of the enabled parameters, in the order the chip reports them.
+    fn labels(&self) -> Vec<&'static str> {
+        self.enabled_metrics()
+            .iter()
+            .map(|metric| metric(0.0).label())
+            .collect()
+    }
+
+    /// CSV header naming the enabled parameters, e.g. `"EC,TDS,S,SG"`.
+    ///
+    /// Empty when no parameters are enabled.
+    pub fn csv_header(&self) -> String {
+        self.labels().join(",")
+    }
+}
+
+impl Default for OutputStringStatus {
+    /// Every parameter disabled, as `OutputStringStatus::new`.
+    fn default() -> OutputStringStatus {
+        OutputStringStatus::new()
     }
 }
 
 impl fmt::Debug for OutputStringStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "?O,{}", self.to_string())
+        write!(f, "?O,{}", self)
     }
 }
 
+/// The enabled parameters, e.g. `EC,TDS`, or `No output` when none are.
 impl fmt::Display for OutputStringStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        if self.is_empty() {
+            write!(f, "No output")
+        } else {
+            write!(f, "{}", self.csv_header())
+        }
+    }
+}
+
+impl FromStr for OutputStringStatus {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        OutputStringStatus::parse(s)
+    }
+}
+
+/// Whether the chip frames its responses with `*OK`/`*ER` codes.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResponseCodeStatus {
+    Enabled,
+    Disabled,
+}
+
+impl ResponseCodeStatus {
+    /// Parses the result of the "*OK,?" command to query whether response
+    /// codes are enabled.
+    pub fn parse(response: &str) -> Result<ResponseCodeStatus, EzoError> {
+        if response.starts_with("?*OK,") {
+            let rest = response.get(5..).unwrap();
+            match rest {
+                "1" => Ok(ResponseCodeStatus::Enabled),
+                "0" => Ok(ResponseCodeStatus::Disabled),
+                _ => Err(ErrorKind::ResponseParse.into()),
+            }
+        } else {
+            Err(ErrorKind::ResponseParse.into())
+        }
+    }
+}
+
+impl fmt::Debug for ResponseCodeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResponseCodeStatus::Enabled => write!(f, "?*OK,1"),
+            ResponseCodeStatus::Disabled => write!(f, "?*OK,0"),
+        }
+    }
+}
+
+impl fmt::Display for ResponseCodeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResponseCodeStatus::Enabled => write!(f, "enabled"),
+            ResponseCodeStatus::Disabled => write!(f, "disabled"),
+        }
+    }
+}
+
+impl FromStr for ResponseCodeStatus {
+    type Err = EzoError;
+
+    fn from_str(s: &str) -> Result<Self, EzoError> {
+        ResponseCodeStatus::parse(s)
     }
 }
 
+/// Range of specific gravity values that make physical sense.
+const MIN_SPECIFIC_GRAVITY: f64 = 1.0;
+const MAX_SPECIFIC_GRAVITY: f64 = 1.3;
+
+/// Specific gravity the chip reports outside valid conditions.
+const SENTINEL_SPECIFIC_GRAVITY: f64 = 1.0;
+
+/// Validity of a `ProbeMetric` value, as returned by `ProbeMetric::validity`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MetricValidity {
+    /// The value is plausible.
+    Valid,
+    /// The value is the one the chip reports outside valid conditions, so
+    /// it is suspect.
+    Sentinel,
+    /// The value is physically implausible.
+    OutOfRange,
+}
+
+/// Kind of a `ProbeMetric`, without its value. Ordered as the chip reports
+/// them: EC, TDS, S, SG.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MetricKind {
+    ElectricConductivity,
+    TotalDissolvedSolids,
+    Salinity,
+    SpecificGravity,
+}
+
+/// A single sensing metric from a reading.
+///
+/// Its values are `f64`, so it can't implement `Eq` or `Hash`, nor `Ord`.
+/// Sort metrics by kind with `cmp_kind`.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ProbeMetric {
     ElectricConductivity(f64),
@@ -277,7 +854,113 @@ pub enum ProbeMetric {
     SpecificGravity(f64),
 }
 
+impl ProbeMetric {
+    /// Kind of the metric.
+    pub fn kind(&self) -> MetricKind {
+        match *self {
+            ProbeMetric::ElectricConductivity(_) => MetricKind::ElectricConductivity,
+            ProbeMetric::TotalDissolvedSolids(_) => MetricKind::TotalDissolvedSolids,
+            ProbeMetric::Salinity(_) => MetricKind::Salinity,
+            ProbeMetric::SpecificGravity(_) => MetricKind::SpecificGravity,
+        }
+    }
+
+    /// Compares metrics by kind, in EC, TDS, S, SG order, ignoring their
+    /// values, e.g. to sort table columns with `sort_by`.
+    pub fn cmp_kind(&self, other: &ProbeMetric) -> Ordering {
+        self.kind().cmp(&other.kind())
+    }
+
+    /// Label used by the chip's output string for this metric.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            ProbeMetric::ElectricConductivity(_) => "EC",
+            ProbeMetric::TotalDissolvedSolids(_) => "TDS",
+            ProbeMetric::Salinity(_) => "S",
+            ProbeMetric::SpecificGravity(_) => "SG",
+        }
+    }
+
+    /// Value of the metric.
+    pub fn value(&self) -> f64 {
+        match *self {
+            ProbeMetric::ElectricConductivity(v)
+            | ProbeMetric::TotalDissolvedSolids(v)
+            | ProbeMetric::Salinity(v)
+            | ProbeMetric::SpecificGravity(v) => v,
+        }
+    }
+
+    /// Validity of the metric's value.
+    ///
+    /// Only specific gravity is checked: it is meaningful for seawater, from
+    /// `1.000` up to about `1.300`, and anything else is out of range. The
+    /// chip reports exactly `1.000` outside valid conditions, so that value
+    /// is a sentinel.
+    pub fn validity(&self) -> MetricValidity {
+        match *self {
+            ProbeMetric::SpecificGravity(v) if v == SENTINEL_SPECIFIC_GRAVITY => {
+                MetricValidity::Sentinel
+            }
+            ProbeMetric::SpecificGravity(v)
+                if !(MIN_SPECIFIC_GRAVITY..=MAX_SPECIFIC_GRAVITY).contains(&v) =>
+            {
+                MetricValidity::OutOfRange
+            }
+            _ => MetricValidity::Valid,
+        }
+    }
+
+    /// Value of the metric, or `None` when it isn't `MetricValidity::Valid`.
+    pub fn plausible_value(&self) -> Option<f64> {
+        match self.validity() {
+            MetricValidity::Valid => Some(self.value()),
+            _ => None,
+        }
+    }
+
+    /// Whether both metrics are of the same kind, with values that differ by
+    /// no more than `epsilon`.
+    pub fn approx_eq(&self, other: &ProbeMetric, epsilon: f64) -> bool {
+        self.label() == other.label() && within_epsilon(self.value(), other.value(), epsilon)
+    }
+}
+
+impl fmt::Display for ProbeMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProbeMetric::ElectricConductivity(v) => write!(f, "EC: {:.3} µS/cm", v),
+            ProbeMetric::TotalDissolvedSolids(v) => write!(f, "TDS: {:.3} ppm", v),
+            ProbeMetric::Salinity(v) => write!(f, "Salinity: {:.3} PSU", v),
+            ProbeMetric::SpecificGravity(v) => write!(f, "SG: {:.3}", v),
+        }
+    }
+}
+
+/// Decimals the chip reports conductivity with.
+pub const DEFAULT_READING_PRECISION: usize = 3;
+
+/// A field of a reading that failed to parse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidField {
+    /// Zero-based position of the field in the reading.
+    pub index: usize,
+    /// Text of the field as received.
+    pub field: String,
+}
+
+impl fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid reading field {}: {:?}", self.index, self.field)
+    }
+}
+
+impl Fail for InvalidField {}
+
 /// Sample reading, can include from `None` to `FourParameters`.
+///
+/// Its values are `f64`, so it can't implement `Eq` or `Hash`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Copy, Clone, PartialEq)]
 pub enum ProbeReading {
     None,
@@ -315,25 +998,272 @@ impl ProbeReading {
             return Ok(ProbeReading::ThreeParameters(_one, _two, _three));
         };
 
-        if let Some(_) = split.next() {
+        if split.next().is_some() {
             return Err(ErrorKind::ResponseParse.into());
         };
 
         Ok(ProbeReading::FourParameters(_one, _two, _three, _four))
     }
-}
 
-impl fmt::Debug for ProbeReading {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &ProbeReading::None => write!(f, "none"),
-            &ProbeReading::OneParameter(a) => write!(f, "{}", a),
-            &ProbeReading::TwoParameters(a, b) => write!(f, "{},{}", a, b),
-            &ProbeReading::ThreeParameters(a, b, c) => write!(f, "{},{},{}", a, b, c),
-            &ProbeReading::FourParameters(a, b, c, d) => write!(f, "{},{},{},{}", a, b, c, d),
+    /// Parses a reading like `parse`, but the error is caused by an
+    /// `InvalidField` naming the field that failed, so it can be recovered
+    /// with `errors::invalid_field`.
+    ///
+    /// A fifth field is reported as invalid at index 4.
+    pub fn checked_parse(response: &str) -> Result<ProbeReading, EzoError> {
+        let mut values = [0.0; 4];
+        let mut len = 0;
+        for (index, field) in response.split(',').enumerate() {
+            let value = match f64::from_str(field) {
+                Ok(value) if index < values.len() => value,
+                _ => {
+                    let field = field.to_string();
+                    Err(InvalidField { index, field }).context(ErrorKind::ResponseParse)?
+                }
+            };
+            values[index] = value;
+            len += 1;
         }
+        Ok(match len {
+            1 => ProbeReading::OneParameter(values[0]),
+            2 => ProbeReading::TwoParameters(values[0], values[1]),
+            3 => ProbeReading::ThreeParameters(values[0], values[1], values[2]),
+            _ => ProbeReading::FourParameters(values[0], values[1], values[2], values[3]),
+        })
     }
-}
+
+    /// Parses a reading that must hold exactly `n` values, e.g. the number
+    /// of parameters enabled in the output string.
+    ///
+    /// Fails with `ErrorKind::ResponseParse` when the count differs.
+    pub fn parse_expecting(response: &str, n: usize) -> Result<ProbeReading, EzoError> {
+        let reading = ProbeReading::parse(response)?;
+        if reading.len() != n {
+            return Err(ErrorKind::ResponseParse.into());
+        }
+        Ok(reading)
+    }
+
+    /// Number of values in the reading, from 0 to 4.
+    pub fn len(&self) -> usize {
+        match *self {
+            ProbeReading::None => 0,
+            ProbeReading::OneParameter(..) => 1,
+            ProbeReading::TwoParameters(..) => 2,
+            ProbeReading::ThreeParameters(..) => 3,
+            ProbeReading::FourParameters(..) => 4,
+        }
+    }
+
+    /// Returns `true` for the `None` reading.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value at `index`, in output-string order.
+    pub fn get(&self, index: usize) -> Option<f64> {
+        match (*self, index) {
+            (ProbeReading::OneParameter(a), 0) => Some(a),
+            (ProbeReading::TwoParameters(a, _), 0) => Some(a),
+            (ProbeReading::TwoParameters(_, b), 1) => Some(b),
+            (ProbeReading::ThreeParameters(a, _, _), 0) => Some(a),
+            (ProbeReading::ThreeParameters(_, b, _), 1) => Some(b),
+            (ProbeReading::ThreeParameters(_, _, c), 2) => Some(c),
+            (ProbeReading::FourParameters(a, _, _, _), 0) => Some(a),
+            (ProbeReading::FourParameters(_, b, _, _), 1) => Some(b),
+            (ProbeReading::FourParameters(_, _, c, _), 2) => Some(c),
+            (ProbeReading::FourParameters(_, _, _, d), 3) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the values of the reading, in output-string order.
+    pub fn iter(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+
+    /// Values of the reading separated by commas, each with `decimals`
+    /// digits after the decimal point, e.g. `DEFAULT_READING_PRECISION`.
+    pub fn display_with_precision(&self, decimals: usize) -> String {
+        if self.is_empty() {
+            return "none".to_string();
+        }
+        self.iter()
+            .map(|value| format!("{:.*}", decimals, value))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// JSON object mapping the parameters enabled in `status` to the values
+    /// of the reading, e.g. `{"EC":12.5,"TDS":6.3}`.
+    ///
+    /// Built by hand, so it doesn't need the `serde` feature. Values past the
+    /// enabled parameters are left out, and non-finite values are written as
+    /// `null`. The `None` reading is `{}`.
+    pub fn to_json(&self, status: &OutputStringStatus) -> String {
+        let fields = status
+            .labels()
+            .into_iter()
+            .zip(self.iter())
+            .map(|(label, value)| format!("\"{}\":{}", label, json_number(value)))
+            .collect::<Vec<String>>()
+            .join(",");
+        format!("{{{}}}", fields)
+    }
+
+    /// Whether both readings hold the same number of values, each differing
+    /// by no more than `epsilon`.
+    pub fn approx_eq(&self, other: &ProbeReading, epsilon: f64) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(a, b)| within_epsilon(a, b, epsilon))
+    }
+
+    /// Change of every value since the `previous` reading.
+    ///
+    /// Fails with `ErrorKind::ResponseParse` when the readings hold a
+    /// different number of values.
+    pub fn delta(&self, previous: &ProbeReading) -> Result<ProbeReading, EzoError> {
+        use self::ProbeReading::*;
+
+        match (*self, *previous) {
+            (ProbeReading::None, ProbeReading::None) => Ok(ProbeReading::None),
+            (OneParameter(a), OneParameter(pa)) => Ok(OneParameter(a - pa)),
+            (TwoParameters(a, b), TwoParameters(pa, pb)) => Ok(TwoParameters(a - pa, b - pb)),
+            (ThreeParameters(a, b, c), ThreeParameters(pa, pb, pc)) => {
+                Ok(ThreeParameters(a - pa, b - pb, c - pc))
+            }
+            (FourParameters(a, b, c, d), FourParameters(pa, pb, pc, pd)) => {
+                Ok(FourParameters(a - pa, b - pb, c - pc, d - pd))
+            }
+            _ => Err(ErrorKind::ResponseParse.into()),
+        }
+    }
+}
+
+/// Range of TDS conversion factors the chip accepts, bounding TDS relative
+/// to conductivity.
+const MIN_TDS_FACTOR: f64 = 0.01;
+const MAX_TDS_FACTOR: f64 = 1.0;
+
+/// Range of salinity (PSU) per µS/cm of conductivity, loosely around the
+/// PSS-78 ratio at 25°C.
+const MIN_SALINITY_PER_EC: f64 = 0.0003;
+const MAX_SALINITY_PER_EC: f64 = 0.001;
+
+/// Slack for values the chip rounds to two decimals.
+const ROUNDING_SLACK: f64 = 0.01;
+
+/// A `ProbeReading` whose values are labeled by the metric they measure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabeledReading {
+    metrics: Vec<ProbeMetric>,
+}
+
+impl LabeledReading {
+    /// Labels the values of `reading` with the parameters enabled in `status`.
+    ///
+    /// Fails with `ErrorKind::ResponseParse` when the number of values does not
+    /// match the number of enabled parameters.
+    pub fn new(
+        reading: &ProbeReading,
+        status: &OutputStringStatus,
+    ) -> Result<LabeledReading, EzoError> {
+        if status.enabled_count() != reading.len() {
+            return Err(ErrorKind::ResponseParse.into());
+        }
+        let metrics = status
+            .enabled_metrics()
+            .iter()
+            .zip(reading.iter())
+            .map(|(metric, value)| metric(value))
+            .collect();
+        let labeled = LabeledReading { metrics };
+        #[cfg(feature = "logging")]
+        {
+            if !labeled.is_consistent() {
+                log::warn!(
+                    "reading {:?} doesn't fit the EC,TDS,S,SG order, is the output string misconfigured?",
+                    labeled.metrics
+                );
+            }
+        }
+        Ok(labeled)
+    }
+
+    /// Checks that the values relate as the datasheet's EC, TDS, S, SG
+    /// output order implies: TDS is conductivity times a factor from 0.01 to
+    /// 1.00, salinity follows conductivity, and specific gravity is
+    /// plausible.
+    ///
+    /// The chip derives every value from conductivity, so a violation hints
+    /// at values labeled with the wrong `OutputStringStatus`. It may also
+    /// flag genuine readings, e.g. the `0.000` specific gravity of a K 0.1
+    /// probe, so `new` doesn't enforce it; with the `logging` feature, it
+    /// only warns.
+    pub fn is_consistent(&self) -> bool {
+        let ec = self.metrics.iter().find_map(|metric| match *metric {
+            ProbeMetric::ElectricConductivity(v) => Some(v),
+            _ => None,
+        });
+        let within = |value: f64, min: f64, max: f64| {
+            value >= min - ROUNDING_SLACK && value <= max + ROUNDING_SLACK
+        };
+        self.metrics.iter().all(|metric| match (*metric, ec) {
+            (ProbeMetric::TotalDissolvedSolids(tds), Some(ec)) => {
+                within(tds, ec * MIN_TDS_FACTOR, ec * MAX_TDS_FACTOR)
+            }
+            (ProbeMetric::Salinity(s), Some(ec)) => {
+                within(s, ec * MIN_SALINITY_PER_EC, ec * MAX_SALINITY_PER_EC)
+            }
+            (ProbeMetric::SpecificGravity(_), _) => metric.validity() != MetricValidity::OutOfRange,
+            _ => true,
+        })
+    }
+
+    /// The labeled metrics, in the order the chip reports them.
+    pub fn metrics(&self) -> &[ProbeMetric] {
+        &self.metrics
+    }
+
+    /// CSV row with the values, matching `OutputStringStatus::csv_header`.
+    pub fn csv_row(&self) -> String {
+        self.metrics
+            .iter()
+            .map(|metric| metric.value().to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// Lowercase metric keys paired with their values, e.g. `("ec", 12.5)`,
+    /// ready to be published as MQTT topic suffixes.
+    pub fn to_kv_pairs(&self) -> Vec<(String, f64)> {
+        self.metrics
+            .iter()
+            .map(|metric| (metric.label().to_lowercase(), metric.value()))
+            .collect()
+    }
+}
+
+impl From<ProbeReading> for Vec<f64> {
+    fn from(reading: ProbeReading) -> Vec<f64> {
+        reading.iter().collect()
+    }
+}
+
+impl fmt::Debug for ProbeReading {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProbeReading::None => write!(f, "none"),
+            ProbeReading::OneParameter(a) => write!(f, "{}", a),
+            ProbeReading::TwoParameters(a, b) => write!(f, "{},{}", a, b),
+            ProbeReading::ThreeParameters(a, b, c) => write!(f, "{},{},{}", a, b, c),
+            ProbeReading::FourParameters(a, b, c, d) => write!(f, "{},{},{},{}", a, b, c, d),
+        }
+    }
+}
 
 impl fmt::Display for ProbeReading {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -341,27 +1271,719 @@ impl fmt::Display for ProbeReading {
     }
 }
 
+/// Earliest EC firmware that accepts the `RT,t` command.
+#[cfg(feature = "std")]
+const RT_FIRMWARE: (u8, u8) = (2, 10);
+
+/// Calibration data gathered from successive `Export` responses, until the
+/// chip reports `*DONE`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportedCalibration {
+    lines: Vec<String>,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl ExportedCalibration {
+    pub fn new() -> ExportedCalibration {
+        ExportedCalibration::default()
+    }
+
+    /// Adds the response to the next `Export` command. Returns `true` once the
+    /// export is done.
+    ///
+    /// Fails with `ErrorKind::ResponseParse` when a line arrives after `*DONE`.
+    pub fn push(&mut self, exported: Exported) -> Result<bool, EzoError> {
+        if self.done {
+            return Err(ErrorKind::ResponseParse.into());
+        }
+        match exported {
+            Exported::ExportString(line) => self.lines.push(line),
+            Exported::Done => self.done = true,
+        }
+        Ok(self.done)
+    }
+
+    /// Whether `*DONE` has been received.
+    pub fn is_complete(&self) -> bool {
+        self.done
+    }
+
+    /// The lines received so far, in export order.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Checks the export against what `ExportInfo` reported, and yields its
+    /// lines, ready to be imported back.
+    ///
+    /// Fails with `ErrorKind::ResponseParse` when the export is not done, or
+    /// when the number of lines or bytes received does not match `info`.
+    pub fn finish(self, info: &ExportedInfo) -> Result<Vec<String>, EzoError> {
+        let total_bytes: usize = self.lines.iter().map(|line| line.len()).sum();
+        if !self.done
+            || self.lines.len() != info.lines as usize
+            || total_bytes != info.total_bytes as usize
+        {
+            return Err(ErrorKind::ResponseParse.into());
+        }
+        Ok(self.lines)
+    }
+}
+
+/// Parses the `major.minor` firmware version of the chip, e.g. `(2, 10)`
+/// for `"2.10"`.
+#[cfg(feature = "std")]
+pub fn firmware_version(info: &DeviceInfo) -> Option<(u8, u8)> {
+    let mut split = info.firmware.trim().split('.');
+    let major = split.next()?.parse().ok()?;
+    let minor = split.next()?.parse().ok()?;
+    match split.next() {
+        None => Some((major, minor)),
+        _ => None,
+    }
+}
+
+/// Returns `true` when the chip's firmware supports the `RT,t` command.
+/// Unreadable versions are assumed not to.
+#[cfg(feature = "std")]
+pub fn firmware_supports_rt(info: &DeviceInfo) -> bool {
+    firmware_version(info).is_some_and(|version| version >= RT_FIRMWARE)
+}
+
+/// Lowest and highest supply voltages considered healthy. The chip runs on
+/// 3.3 V to 5 V; readings get erratic once it browns out.
+#[cfg(feature = "std")]
+const MIN_HEALTHY_VCC: f64 = 3.1;
+#[cfg(feature = "std")]
+const MAX_HEALTHY_VCC: f64 = 5.5;
+
+/// Returns `true` when the supply voltage reported by `Status` lies
+/// within `min..=max` volts.
+#[cfg(feature = "std")]
+pub fn voltage_within(status: &DeviceStatus, min: f64, max: f64) -> bool {
+    (min..=max).contains(&status.vcc_voltage)
+}
+
+/// Returns `true` when the supply voltage reported by `Status` is within
+/// the chip's operating range, with some margin.
+#[cfg(feature = "std")]
+pub fn is_voltage_healthy(status: &DeviceStatus) -> bool {
+    voltage_within(status, MIN_HEALTHY_VCC, MAX_HEALTHY_VCC)
+}
+
+/// Strips the `*OK`/`*ER` framing the chip adds once response codes are
+/// enabled with `*OK,1`, returning the response it wraps.
+///
+/// A bare `*OK` leaves an empty response, as for any acknowledged command,
+/// while `*ER` fails with `ErrorKind::DeviceErrorResponse`. Unframed
+/// responses are returned unchanged.
+pub fn strip_framing(response: &str) -> Result<&str, EzoError> {
+    let response = response.trim_matches(|c| c == '\r' || c == '\n');
+    if response.starts_with("*ER") || response.ends_with("*ER") {
+        return Err(ErrorKind::DeviceErrorResponse.into());
+    }
+    let separators: &[char] = &[',', '\r', '\n'];
+    let response = match response.strip_prefix("*OK") {
+        Some(rest) => rest.trim_start_matches(separators),
+        None => response,
+    };
+    let response = match response.strip_suffix("*OK") {
+        Some(rest) => rest.trim_end_matches(separators),
+        None => response,
+    };
+    Ok(response)
+}
+
+/// Marker that starts the notification the chip sends after restarting,
+/// e.g. after a brown-out.
+const RESTART_MARKER: &str = "*RS";
+
+/// Reason of the restart announced by `response`, when it starts with the
+/// `*RS` restart marker, or `None` for any other response.
+///
+/// The marker may be followed by a `Status` reason code, as in `*RS,B`. A
+/// bare `*RS`, or an unknown code, yields `RestartReason::Unknown`.
+pub fn parse_restart(response: &str) -> Option<RestartReason> {
+    let rest = response.trim().strip_prefix(RESTART_MARKER)?;
+    let code = rest.trim_start_matches(',');
+    Some(RestartReason::parse(code).unwrap_or(RestartReason::Unknown))
+}
+
+/// Parses every response in `buffer`, as received when several replies
+/// arrive buffered together, e.g. through an I2C-to-UART bridge.
+///
+/// Responses are delimited by carriage returns or newlines, and blank ones
+/// are skipped. A `*OK` right after a response only frames it, so it isn't
+/// reported on its own. Each response is parsed with `ResponseKind::parse`,
+/// so a malformed one yields an error without spoiling the rest.
+pub fn parse_many(buffer: &str) -> Vec<Result<ResponseKind, EzoError>> {
+    let mut responses = Vec::new();
+    let mut after_response = false;
+    for response in buffer
+        .split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|response| !response.is_empty())
+    {
+        if response == "*OK" && after_response {
+            after_response = false;
+            continue;
+        }
+        after_response = response != "*OK" && response != "*ER";
+        responses.push(ResponseKind::parse(response));
+    }
+    responses
+}
+
+/// Trims the whitespace and carriage returns that some I2C-to-UART bridges
+/// leave around a response.
+pub fn normalize_response(response: &str) -> &str {
+    response.trim()
+}
+
+/// Value of a `ReadingContext` entry.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextValue {
+    Number(f64),
+    Text(String),
+}
+
+impl From<f64> for ContextValue {
+    fn from(value: f64) -> ContextValue {
+        ContextValue::Number(value)
+    }
+}
+
+impl From<String> for ContextValue {
+    fn from(value: String) -> ContextValue {
+        ContextValue::Text(value)
+    }
+}
+
+impl<'a> From<&'a str> for ContextValue {
+    fn from(value: &'a str) -> ContextValue {
+        ContextValue::Text(value.to_string())
+    }
+}
+
+/// Metadata kept alongside a reading, e.g. the location of the probe or
+/// the values of other environmental sensors, keyed by name.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadingContext(BTreeMap<String, ContextValue>);
+
+impl ReadingContext {
+    pub fn new() -> ReadingContext {
+        ReadingContext::default()
+    }
+
+    /// Sets the entry for `key`, returning its previous value.
+    pub fn insert<K: Into<String>, V: Into<ContextValue>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Option<ContextValue> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ContextValue> {
+        self.0.get(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<ContextValue> {
+        self.0.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Entries in order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ContextValue)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+}
+
+/// A reading paired with the time it was taken and the output configuration
+/// that labels its values.
+///
+/// `context` holds any metadata the caller attaches; it is left out of the
+/// serialized form when empty.
+#[cfg(feature = "chrono")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampedReading {
+    pub timestamp: DateTime<Utc>,
+    pub reading: ProbeReading,
+    pub status: OutputStringStatus,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "ReadingContext::is_empty")
+    )]
+    pub context: ReadingContext,
+}
+
+#[cfg(feature = "chrono")]
+impl TimestampedReading {
+    /// Attaches `value` to the reading under `key`.
+    pub fn with_context<K: Into<String>, V: Into<ContextValue>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> TimestampedReading {
+        self.context.insert(key, value);
+        self
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TimestampedReading {
+    /// JSON object with the RFC 3339 timestamp, the reading as
+    /// `ProbeReading::to_json` labels it, and the context when it isn't
+    /// empty, e.g.
+    /// `{"timestamp":"2018-03-14T12:30:00Z","reading":{"EC":12.5}}`.
+    ///
+    /// This is meant to be read by people and dashboards, and can't be
+    /// deserialized back; with the `serde` feature, serialize the reading
+    /// itself instead.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"timestamp\":\"{}\",\"reading\":{}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.reading.to_json(&self.status)
+        );
+        if !self.context.is_empty() {
+            let entries = self
+                .context
+                .iter()
+                .map(|(key, value)| {
+                    let value = match *value {
+                        ContextValue::Number(v) => json_number(v),
+                        ContextValue::Text(ref text) => json_string(text),
+                    };
+                    format!("{}:{}", json_string(key), value)
+                })
+                .collect::<Vec<String>>()
+                .join(",");
+            json.push_str(&format!(",\"context\":{{{}}}", entries));
+        }
+        json.push('}');
+        json
+    }
+}
+
+/// Writes `TimestampedReading`s as JSON Lines, one object per line, e.g. to
+/// keep a durable log of a long-running reading loop.
+///
+/// With the `serde` feature, each line is the reading's serialized form, and
+/// can be deserialized back. Otherwise, it's the `to_json` object.
+#[cfg(all(feature = "std", feature = "chrono"))]
+#[derive(Debug)]
+pub struct JsonLinesLogger<W> {
+    writer: W,
+}
+
+#[cfg(all(feature = "std", feature = "chrono"))]
+impl<W: io::Write> JsonLinesLogger<W> {
+    pub fn new(writer: W) -> JsonLinesLogger<W> {
+        JsonLinesLogger { writer }
+    }
+
+    /// Writes `reading` as one line, and flushes it, so that a crash loses
+    /// at most the line being written.
+    ///
+    /// Write errors are returned as is, and the logger can be used again
+    /// afterwards, e.g. once a full disk has been cleared.
+    pub fn log(&mut self, reading: &TimestampedReading) -> io::Result<()> {
+        self.write_reading(reading)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    #[cfg(feature = "serde")]
+    fn write_reading(&mut self, reading: &TimestampedReading) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, reading)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "serde"))]
+    fn write_reading(&mut self, reading: &TimestampedReading) -> io::Result<()> {
+        self.writer.write_all(reading.to_json().as_bytes())
+    }
+
+    /// The underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwraps the logger, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// JSON number for `value`, or `null` when it isn't finite.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// JSON string literal for `text`, quoted and escaped.
+#[cfg(feature = "chrono")]
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// A reading kept together with the response string it was parsed from,
+/// e.g. for audit logs.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawReading {
+    pub raw: String,
+    pub parsed: ProbeReading,
+}
+
+impl RawReading {
+    /// Parses `response` as a `ProbeReading`, keeping `response` as is.
+    pub fn parse(response: &str) -> Result<RawReading, EzoError> {
+        Ok(RawReading {
+            raw: response.to_string(),
+            parsed: ProbeReading::parse(response)?,
+        })
+    }
+}
+
+/// Serde definitions for the responses defined in `ezo_common`.
+#[cfg(all(feature = "serde", feature = "std"))]
+mod remote {
+    use super::{DeviceInfo, DeviceStatus, RestartReason};
+    use alloc::string::String;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "DeviceInfo")]
+    struct DeviceInfoDef {
+        device: String,
+        firmware: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "RestartReason")]
+    enum RestartReasonDef {
+        PoweredOff,
+        SoftwareReset,
+        BrownOut,
+        Watchdog,
+        Unknown,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "DeviceStatus")]
+    struct DeviceStatusDef {
+        #[serde(with = "RestartReasonDef")]
+        restart_reason: RestartReason,
+        vcc_voltage: f64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Result<DeviceInfo, String>")]
+    pub enum DeviceInfoResult {
+        Ok(#[serde(with = "DeviceInfoDef")] DeviceInfo),
+        Err(String),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Result<DeviceStatus, String>")]
+    pub enum DeviceStatusResult {
+        Ok(#[serde(with = "DeviceStatusDef")] DeviceStatus),
+        Err(String),
+    }
+}
+
+/// State of the chip, gathered by querying everything at once.
+///
+/// Each field holds the query's response, or the message of the error it
+/// failed with, so one failed query doesn't spoil the rest.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSnapshot {
+    #[cfg_attr(feature = "serde", serde(with = "remote::DeviceInfoResult"))]
+    pub info: Result<DeviceInfo, String>,
+    #[cfg_attr(feature = "serde", serde(with = "remote::DeviceStatusResult"))]
+    pub status: Result<DeviceStatus, String>,
+    pub calibration: Result<CalibrationStatus, String>,
+    pub probe: Result<ProbeType, String>,
+    pub output: Result<OutputStringStatus, String>,
+    pub compensation: Result<CompensationValue, String>,
+    pub reading: Result<ProbeReading, String>,
+}
+
+#[cfg(feature = "std")]
+impl DeviceSnapshot {
+    /// Whether every query succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.info.is_ok()
+            && self.status.is_ok()
+            && self.calibration.is_ok()
+            && self.probe.is_ok()
+            && self.output.is_ok()
+            && self.compensation.is_ok()
+            && self.reading.is_ok()
+    }
+}
+
+/// Response to a command that the chip only acknowledges.
+///
+/// Ack commands yield `ResponseStatus::Ack`, which converts into this type
+/// for matching and logging.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Ack;
+
+impl fmt::Display for Ack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OK")
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ResponseStatus> for Ack {
+    fn from(status: ResponseStatus) -> Ack {
+        match status {
+            ResponseStatus::Ack => Ack,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Ack> for ResponseStatus {
+    fn from(_: Ack) -> ResponseStatus {
+        ResponseStatus::Ack
+    }
+}
+
+impl From<Ack> for ResponseKind {
+    fn from(_: Ack) -> ResponseKind {
+        ResponseKind::Ack
+    }
+}
+
+/// Any response from the EC EZO chip, identified by its prefix.
+///
+/// Holds `f64` values, so it can't implement `Eq` or `Hash`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ResponseKind {
+    Ack,
+    Calibration(CalibrationStatus),
+    ProbeType(ProbeType),
+    Compensation(CompensationValue),
+    Output(OutputStringStatus),
+    TdsFactor(TdsFactor),
+    ResponseCode(ResponseCodeStatus),
+    Reading(ProbeReading),
+    /// The chip announced it restarted, and should be set up again.
+    Restarted(RestartReason),
+}
+
+/// The variant of `ResponseKind` a response parses into, without its value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResponseType {
+    Ack,
+    Calibration,
+    ProbeType,
+    Compensation,
+    Output,
+    TdsFactor,
+    ResponseCode,
+    Reading,
+    Restarted,
+}
+
+/// Response types whose `ResponseType` is known before running a command.
+pub trait TypedResponse {
+    const RESPONSE_TYPE: ResponseType;
+}
+
+macro_rules! impl_typed_response {
+    ($($response:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl TypedResponse for $response {
+                const RESPONSE_TYPE: ResponseType = ResponseType::$variant;
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "std")]
+impl_typed_response!(ResponseStatus => Ack);
+
+impl_typed_response!(
+    Ack => Ack,
+    CalibrationStatus => Calibration,
+    ProbeType => ProbeType,
+    CompensationValue => Compensation,
+    OutputStringStatus => Output,
+    TdsFactor => TdsFactor,
+    ResponseCodeStatus => ResponseCode,
+    ProbeReading => Reading,
+);
+
+/// Response of a command run through dynamic dispatch, where the concrete
+/// response type isn't known to the caller.
+pub type AnyResponse = ResponseKind;
+
+impl fmt::Display for ResponseKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResponseKind::Ack => write!(f, "{}", Ack),
+            ResponseKind::Calibration(ref status) => write!(f, "{}", status),
+            ResponseKind::ProbeType(ref probe) => write!(f, "{}", probe),
+            ResponseKind::Compensation(ref value) => write!(f, "{}", value),
+            ResponseKind::Output(ref status) => write!(f, "{}", status),
+            ResponseKind::TdsFactor(ref factor) => write!(f, "{}", factor),
+            ResponseKind::ResponseCode(ref status) => write!(f, "{}", status),
+            ResponseKind::Reading(ref reading) => write!(f, "{}", reading),
+            ResponseKind::Restarted(ref reason) => write!(f, "restarted: {}", reason),
+        }
+    }
+}
+
+impl ResponseKind {
+    /// Parses `response` with the parser matching its prefix. Replies
+    /// without a known prefix are parsed as a `ProbeReading`, and empty ones
+    /// are an `Ack`.
+    ///
+    /// Any `*OK`/`*ER` framing is handled by `strip_framing` first. The
+    /// `*RS` restart notification is a `Restarted` response, not an error.
+    pub fn parse(response: &str) -> Result<ResponseKind, EzoError> {
+        let response = strip_framing(response)?;
+        if response.is_empty() {
+            Ok(ResponseKind::Ack)
+        } else if let Some(reason) = parse_restart(response) {
+            Ok(ResponseKind::Restarted(reason))
+        } else if response.starts_with("?CAL,") {
+            CalibrationStatus::parse(response).map(ResponseKind::Calibration)
+        } else if response.starts_with("?K,") {
+            ProbeType::parse(response).map(ResponseKind::ProbeType)
+        } else if response.starts_with("?T,") {
+            CompensationValue::parse(response).map(ResponseKind::Compensation)
+        } else if response.starts_with("?O,") {
+            OutputStringStatus::parse(response).map(ResponseKind::Output)
+        } else if response.starts_with("?TDS,") {
+            TdsFactor::parse(response).map(ResponseKind::TdsFactor)
+        } else if response.starts_with("?*OK,") {
+            ResponseCodeStatus::parse(response).map(ResponseKind::ResponseCode)
+        } else {
+            ProbeReading::parse(response).map(ResponseKind::Reading)
+        }
+    }
+
+    /// The variant of the response, without its value.
+    pub fn response_type(&self) -> ResponseType {
+        match *self {
+            ResponseKind::Ack => ResponseType::Ack,
+            ResponseKind::Calibration(_) => ResponseType::Calibration,
+            ResponseKind::ProbeType(_) => ResponseType::ProbeType,
+            ResponseKind::Compensation(_) => ResponseType::Compensation,
+            ResponseKind::Output(_) => ResponseType::Output,
+            ResponseKind::TdsFactor(_) => ResponseType::TdsFactor,
+            ResponseKind::ResponseCode(_) => ResponseType::ResponseCode,
+            ResponseKind::Reading(_) => ResponseType::Reading,
+            ResponseKind::Restarted(_) => ResponseType::Restarted,
+        }
+    }
+}
+
+macro_rules! impl_parse_lenient {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl $name {
+                /// Parses `response` like `parse`, after trimming it with
+                /// `normalize_response`.
+                pub fn parse_lenient(response: &str) -> Result<$name, EzoError> {
+                    $name::parse(normalize_response(response))
+                }
+            }
+        )*
+    };
+}
+
+impl_parse_lenient!(
+    CalibrationStatus,
+    CompensationValue,
+    TdsFactor,
+    ProbeType,
+    OutputStringStatus,
+    ResponseCodeStatus,
+    ProbeReading,
+    ResponseKind,
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+    #[cfg(not(feature = "std"))]
+    extern crate std;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn converts_calibration_status_from_point_count() {
+        for &(points, status) in &[
+            (0, CalibrationStatus::NotCalibrated),
+            (1, CalibrationStatus::OnePoint),
+            (2, CalibrationStatus::TwoPoint),
+        ] {
+            assert_eq!(CalibrationStatus::try_from(points).unwrap(), status);
+            assert_eq!(u8::from(status), points);
+        }
+        let err = CalibrationStatus::try_from(3).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseParse);
+    }
 
     #[test]
     fn parses_calibration_status() {
         let response = "?CAL,1";
         assert_eq!(
-            CalibrationStatus::parse(&response).unwrap(),
+            CalibrationStatus::parse(response).unwrap(),
             CalibrationStatus::OnePoint
         );
 
         let response = "?CAL,2";
         assert_eq!(
-            CalibrationStatus::parse(&response).unwrap(),
+            CalibrationStatus::parse(response).unwrap(),
             CalibrationStatus::TwoPoint
         );
 
         let response = "?CAL,0";
         assert_eq!(
-            CalibrationStatus::parse(&response).unwrap(),
+            CalibrationStatus::parse(response).unwrap(),
             CalibrationStatus::NotCalibrated
         );
     }
@@ -369,61 +1991,61 @@ mod tests {
     #[test]
     fn parsing_invalid_calibration_status_yields_error() {
         let response = "";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
 
         let response = "?CAL,";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
 
         let response = "?CAL,2.";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
 
         let response = "?CAL,-1";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
 
         let response = "?CAL,4";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
 
         let response = "?CAL,b";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
 
         let response = "?CAL,1,";
-        assert!(CalibrationStatus::parse(&response).is_err());
+        assert!(CalibrationStatus::parse(response).is_err());
     }
 
     #[test]
     fn parses_probe_type_status() {
         let response = "?K,0.1";
-        assert_eq!(ProbeType::parse(&response).unwrap(), ProbeType::PointOne);
+        assert_eq!(ProbeType::parse(response).unwrap(), ProbeType::PointOne);
 
         let response = "?K,1.0";
-        assert_eq!(ProbeType::parse(&response).unwrap(), ProbeType::One);
+        assert_eq!(ProbeType::parse(response).unwrap(), ProbeType::One);
 
         let response = "?K,10.0";
-        assert_eq!(ProbeType::parse(&response).unwrap(), ProbeType::Ten);
+        assert_eq!(ProbeType::parse(response).unwrap(), ProbeType::Ten);
     }
 
     #[test]
     fn parsing_invalid_probe_type_status_yields_error() {
         let response = "";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
 
         let response = "?K,";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
 
         let response = "?K,2.";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
 
         let response = "?K,-1";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
 
         let response = "?K,4";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
 
         let response = "?K,b";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
 
         let response = "?K,1,";
-        assert!(ProbeType::parse(&response).is_err());
+        assert!(ProbeType::parse(response).is_err());
     }
 
     #[test]
@@ -546,6 +2168,42 @@ mod tests {
         assert!(ProbeReading::parse(response).is_err());
     }
 
+    #[test]
+    fn checked_parse_matches_parse() {
+        for response in &["1.0", "1.0,2.0", "1.0,2.0,3.0", "1.0,2.0,3.0,4.0"] {
+            assert_eq!(
+                ProbeReading::checked_parse(response).unwrap(),
+                ProbeReading::parse(response).unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn checked_parse_reports_bad_second_field() {
+        let error = ProbeReading::checked_parse("1413.0,7x3.0,0.7").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ResponseParse);
+        assert_eq!(
+            crate::errors::invalid_field(&error),
+            Some(InvalidField {
+                index: 1,
+                field: "7x3.0".to_string(),
+            })
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn checked_parse_reports_bad_fourth_field() {
+        let error = ProbeReading::checked_parse("1413.0,763.0,0.7,").unwrap_err();
+        let field = crate::errors::invalid_field(&error).unwrap();
+        assert_eq!(field.index, 3);
+        assert_eq!(field.field, "");
+
+        let error = ProbeReading::checked_parse("1.0,2.0,3.0,4.0,5.0").unwrap_err();
+        assert_eq!(crate::errors::invalid_field(&error).unwrap().index, 4);
+    }
+
     #[test]
     fn parses_output_string_status() {
         let response = "?O,EC";
@@ -824,4 +2482,1232 @@ mod tests {
         let response = "?T,1.2,43";
         assert!(CompensationValue::parse(response).is_err());
     }
+
+    #[test]
+    fn parses_tds_factor() {
+        let response = "?TDS,0.54";
+        assert_eq!(TdsFactor::parse(response).unwrap(), TdsFactor(0.54));
+
+        let response = "?TDS,1";
+        assert_eq!(TdsFactor::parse(response).unwrap(), TdsFactor(1.0));
+    }
+
+    #[test]
+    fn parsing_invalid_tds_factor_yields_error() {
+        let response = "";
+        assert!(TdsFactor::parse(response).is_err());
+
+        let response = "?TDS,";
+        assert!(TdsFactor::parse(response).is_err());
+
+        let response = "?TDS,0.5a";
+        assert!(TdsFactor::parse(response).is_err());
+
+        let response = "?TDS,0.5,1";
+        assert!(TdsFactor::parse(response).is_err());
+    }
+
+    #[test]
+    fn converts_temperature_compensation_value_between_scales() {
+        let value = CompensationValue::from_fahrenheit(77.0);
+        assert!((value.0 - 25.0).abs() < 1e-9);
+        assert!((value.as_fahrenheit() - 77.0).abs() < 1e-9);
+        assert!((value.as_kelvin() - 298.15).abs() < 1e-9);
+
+        let value = CompensationValue::from_kelvin(273.15);
+        assert!(value.0.abs() < 1e-9);
+        assert!((value.as_fahrenheit() - 32.0).abs() < 1e-9);
+
+        let value = CompensationValue::from_scale(19.5, TemperatureScale::Celsius);
+        assert_eq!(value, CompensationValue(19.5));
+        assert_eq!(value.as_scale(TemperatureScale::Celsius), 19.5);
+    }
+
+    #[test]
+    fn temperature_compensation_value_round_trips_through_scales() {
+        for &celsius in &[-5.0, 0.0, 19.5, 37.8, 110.0] {
+            let value = CompensationValue(celsius);
+            let fahrenheit = CompensationValue::from_fahrenheit(value.as_fahrenheit());
+            let kelvin = CompensationValue::from_kelvin(value.as_kelvin());
+            assert!((fahrenheit.0 - celsius).abs() < 1e-9);
+            assert!((kelvin.0 - celsius).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn clamps_compensation_value_to_sensor_range() {
+        for &(celsius, clamped) in &[
+            (-40.0, -5.0),
+            (-5.0, -5.0),
+            (25.0, 25.0),
+            (110.0, 110.0),
+            (110.5, 110.0),
+        ] {
+            assert_eq!(
+                CompensationValue(celsius).clamp_to_sensor_range(),
+                CompensationValue(clamped)
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn checks_compensation_value_against_sensor_range() {
+        for &celsius in &[-5.0, 25.0, 110.0] {
+            let value = CompensationValue(celsius);
+            assert_eq!(value.check_sensor_range().unwrap(), value);
+        }
+        for &celsius in &[-5.001, 110.001] {
+            let error = CompensationValue(celsius).check_sensor_range().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::CommandParse);
+            let range = crate::errors::value_out_of_range(&error).unwrap();
+            assert_eq!((range.value, range.min, range.max), (celsius, -5.0, 110.0));
+        }
+    }
+
+    #[test]
+    fn response_enums_can_be_used_as_map_keys() {
+        let mut probes = HashMap::new();
+        *probes.entry(ProbeType::One).or_insert(0) += 1;
+        *probes.entry(ProbeType::Ten).or_insert(0) += 1;
+        *probes.entry(ProbeType::One).or_insert(0) += 1;
+        assert_eq!(probes[&ProbeType::One], 2);
+        assert_eq!(probes[&ProbeType::Ten], 1);
+
+        let calibrations: HashSet<CalibrationStatus> = vec![
+            CalibrationStatus::OnePoint,
+            CalibrationStatus::OnePoint,
+            CalibrationStatus::NotCalibrated,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(calibrations.len(), 2);
+
+        let statuses: HashSet<ParameterStatus> = vec![
+            ParameterStatus::On,
+            ParameterStatus::Off,
+            ParameterStatus::On,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(statuses.len(), 2);
+    }
+
+    #[test]
+    fn accesses_values_of_empty_sensor_reading() {
+        let reading = ProbeReading::None;
+        assert_eq!(reading.len(), 0);
+        assert!(reading.is_empty());
+        assert_eq!(reading.get(0), None);
+        assert_eq!(reading.iter().count(), 0);
+    }
+
+    #[test]
+    fn accesses_values_of_single_parameter_sensor_reading() {
+        let reading = ProbeReading::OneParameter(12.5);
+        assert_eq!(reading.len(), 1);
+        assert!(!reading.is_empty());
+        assert_eq!(reading.get(0), Some(12.5));
+        assert_eq!(reading.get(1), None);
+        assert_eq!(reading.iter().collect::<Vec<f64>>(), vec![12.5]);
+    }
+
+    #[test]
+    fn accesses_values_of_four_parameters_sensor_reading() {
+        let reading = ProbeReading::FourParameters(12.5, 6.75, 0.01, 1.0);
+        assert_eq!(reading.len(), 4);
+        assert_eq!(reading.get(0), Some(12.5));
+        assert_eq!(reading.get(1), Some(6.75));
+        assert_eq!(reading.get(2), Some(0.01));
+        assert_eq!(reading.get(3), Some(1.0));
+        assert_eq!(reading.get(4), None);
+        assert_eq!(
+            reading.iter().collect::<Vec<f64>>(),
+            vec![12.5, 6.75, 0.01, 1.0]
+        );
+    }
+
+    #[test]
+    fn converts_sensor_reading_into_vec() {
+        let values: Vec<f64> = ProbeReading::None.into();
+        assert!(values.is_empty());
+
+        let values: Vec<f64> = ProbeReading::OneParameter(12.5).into();
+        assert_eq!(values, vec![12.5]);
+
+        let values: Vec<f64> = ProbeReading::TwoParameters(12.5, 6.75).into();
+        assert_eq!(values, vec![12.5, 6.75]);
+
+        let values: Vec<f64> = ProbeReading::ThreeParameters(12.5, 6.75, 0.01).into();
+        assert_eq!(values, vec![12.5, 6.75, 0.01]);
+
+        let values: Vec<f64> = ProbeReading::FourParameters(12.5, 6.75, 0.01, 1.0).into();
+        assert_eq!(values, vec![12.5, 6.75, 0.01, 1.0]);
+    }
+
+    #[test]
+    fn writes_csv_header_for_enabled_parameters() {
+        let cases = [
+            ("?O,EC", "EC"),
+            ("?O,EC,TDS,S,SG", "EC,TDS,S,SG"),
+            ("?O,EC,S", "EC,S"),
+            ("?O,TDS,SG", "TDS,SG"),
+            ("?O,SG", "SG"),
+            ("?O,No output", ""),
+        ];
+        for &(response, header) in cases.iter() {
+            let status = OutputStringStatus::parse(response).unwrap();
+            assert_eq!(status.csv_header(), header);
+        }
+    }
+
+    #[test]
+    fn writes_csv_row_for_labeled_reading() {
+        let status = OutputStringStatus::parse("?O,EC,S").unwrap();
+        let reading = ProbeReading::parse("50000,32.74").unwrap();
+        let labeled = LabeledReading::new(&reading, &status).unwrap();
+        assert_eq!(
+            labeled.metrics(),
+            &[
+                ProbeMetric::ElectricConductivity(50000.0),
+                ProbeMetric::Salinity(32.74),
+            ]
+        );
+        assert_eq!(labeled.csv_row(), "50000,32.74");
+
+        let status = OutputStringStatus::new();
+        let labeled = LabeledReading::new(&ProbeReading::None, &status).unwrap();
+        assert_eq!(status.csv_header(), "");
+        assert_eq!(labeled.csv_row(), "");
+    }
+
+    #[test]
+    fn writes_key_value_pairs_for_labeled_reading() {
+        let status = OutputStringStatus::parse("?O,TDS").unwrap();
+        let labeled = LabeledReading::new(&ProbeReading::OneParameter(0.0), &status).unwrap();
+        assert_eq!(labeled.to_kv_pairs(), vec![("tds".to_string(), 0.0)]);
+
+        let status = OutputStringStatus::parse("?O,EC,TDS,S,SG").unwrap();
+        let reading = ProbeReading::FourParameters(50000.0, 25000.0, 32.74, 1.024);
+        let labeled = LabeledReading::new(&reading, &status).unwrap();
+        assert_eq!(
+            labeled.to_kv_pairs(),
+            vec![
+                ("ec".to_string(), 50000.0),
+                ("tds".to_string(), 25000.0),
+                ("s".to_string(), 32.74),
+                ("sg".to_string(), 1.024),
+            ]
+        );
+
+        let labeled = LabeledReading::new(&ProbeReading::None, &OutputStringStatus::new()).unwrap();
+        assert!(labeled.to_kv_pairs().is_empty());
+    }
+
+    #[test]
+    fn sorts_probe_metrics_by_kind() {
+        let mut metrics = vec![
+            ProbeMetric::SpecificGravity(1.024),
+            ProbeMetric::ElectricConductivity(50000.0),
+            ProbeMetric::Salinity(32.74),
+            ProbeMetric::TotalDissolvedSolids(25000.0),
+        ];
+        metrics.sort_by(ProbeMetric::cmp_kind);
+        assert_eq!(
+            metrics,
+            vec![
+                ProbeMetric::ElectricConductivity(50000.0),
+                ProbeMetric::TotalDissolvedSolids(25000.0),
+                ProbeMetric::Salinity(32.74),
+                ProbeMetric::SpecificGravity(1.024),
+            ]
+        );
+
+        let low = ProbeMetric::Salinity(0.0);
+        let high = ProbeMetric::Salinity(40.0);
+        assert_eq!(low.cmp_kind(&high), Ordering::Equal);
+        assert_eq!(
+            ProbeMetric::SpecificGravity(1.0).cmp_kind(&ProbeMetric::ElectricConductivity(1e6)),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn displays_probe_metric_with_units() {
+        assert_eq!(
+            format!("{}", ProbeMetric::ElectricConductivity(12.5)),
+            "EC: 12.500 µS/cm"
+        );
+        assert_eq!(
+            format!("{}", ProbeMetric::TotalDissolvedSolids(0.0)),
+            "TDS: 0.000 ppm"
+        );
+        assert_eq!(
+            format!("{}", ProbeMetric::Salinity(1.0041)),
+            "Salinity: 1.004 PSU"
+        );
+        assert_eq!(
+            format!("{}", ProbeMetric::SpecificGravity(1.004)),
+            "SG: 1.004"
+        );
+        assert_eq!(format!("{:?}", ProbeMetric::Salinity(1.5)), "Salinity(1.5)");
+    }
+
+    #[test]
+    fn builds_output_string_status_fluently() {
+        let status = OutputStringStatus::new()
+            .with_conductivity(true)
+            .with_tds(true);
+        assert_eq!(status.to_string(), "EC,TDS");
+        assert_eq!(status, OutputStringStatus::parse("?O,EC,TDS").unwrap());
+
+        let status = OutputStringStatus::new()
+            .with_conductivity(true)
+            .with_tds(true)
+            .with_salinity(true)
+            .with_specific_gravity(true);
+        assert_eq!(status.to_string(), "EC,TDS,S,SG");
+
+        let status = status.with_conductivity(false).with_salinity(false);
+        assert_eq!(status.to_string(), "TDS,SG");
+
+        let status = OutputStringStatus::new().with_salinity(false);
+        assert_eq!(status.to_string(), "No output");
+    }
+
+    #[test]
+    fn counts_enabled_output_parameters() {
+        let status = OutputStringStatus::new();
+        assert_eq!(status.enabled_count(), 0);
+        assert!(status.is_empty());
+
+        let status = OutputStringStatus::parse("?O,S").unwrap();
+        assert_eq!(status.enabled_count(), 1);
+        assert!(!status.is_empty());
+
+        let status = OutputStringStatus::parse("?O,EC,TDS,S,SG").unwrap();
+        assert_eq!(status.enabled_count(), 4);
+        assert!(!status.is_empty());
+    }
+
+    #[test]
+    fn labeled_reading_with_consistent_values() {
+        let status = OutputStringStatus::parse("?O,EC,TDS,S,SG").unwrap();
+        let reading = ProbeReading::parse("53064.77,28655.0,35.0,1.026").unwrap();
+        assert!(LabeledReading::new(&reading, &status)
+            .unwrap()
+            .is_consistent());
+
+        let status = OutputStringStatus::parse("?O,TDS,SG").unwrap();
+        let reading = ProbeReading::parse("28655.0,1.026").unwrap();
+        assert!(LabeledReading::new(&reading, &status)
+            .unwrap()
+            .is_consistent());
+
+        let status = OutputStringStatus::parse("?O,EC,TDS,S").unwrap();
+        let reading = ProbeReading::parse("0.00,0.00,0.00").unwrap();
+        assert!(LabeledReading::new(&reading, &status)
+            .unwrap()
+            .is_consistent());
+    }
+
+    #[test]
+    fn labeled_reading_with_inconsistent_values() {
+        use ProbeMetric::*;
+
+        // TDS and EC swapped.
+        let labeled = LabeledReading {
+            metrics: vec![ElectricConductivity(763.0), TotalDissolvedSolids(1413.0)],
+        };
+        assert!(!labeled.is_consistent());
+
+        // Salinity and specific gravity swapped.
+        let labeled = LabeledReading {
+            metrics: vec![
+                ElectricConductivity(53_064.77),
+                Salinity(1.026),
+                SpecificGravity(35.0),
+            ],
+        };
+        assert!(!labeled.is_consistent());
+
+        // TDS read as salinity.
+        let labeled = LabeledReading {
+            metrics: vec![ElectricConductivity(1413.0), Salinity(763.0)],
+        };
+        assert!(!labeled.is_consistent());
+    }
+
+    #[test]
+    fn labeling_inconsistent_values_still_succeeds() {
+        let status = OutputStringStatus::parse("?O,EC,TDS").unwrap();
+        let reading = ProbeReading::TwoParameters(763.0, 1413.0);
+        let labeled = LabeledReading::new(&reading, &status).unwrap();
+        assert!(!labeled.is_consistent());
+
+        // A K 0.1 probe always reports a specific gravity of 0.000.
+        let status = OutputStringStatus::parse("?O,EC,SG").unwrap();
+        let reading = ProbeReading::TwoParameters(1413.0, 0.0);
+        let labeled = LabeledReading::new(&reading, &status).unwrap();
+        assert!(!labeled.is_consistent());
+    }
+
+    #[test]
+    fn labeling_reading_with_mismatched_output_yields_error() {
+        let status = OutputStringStatus::parse("?O,EC,TDS").unwrap();
+        let reading = ProbeReading::OneParameter(12.5);
+        assert!(LabeledReading::new(&reading, &status).is_err());
+    }
+
+    #[test]
+    fn tracks_calibration_detail() {
+        let mut detail = CalibrationDetail::with(CalibrationStatus::NotCalibrated);
+        assert_eq!(detail.completed_points(), 0);
+        assert!(!detail.is_complete());
+
+        detail.set_dry(true);
+        detail.set_low(true);
+        detail.set_status(CalibrationStatus::OnePoint);
+        assert_eq!(detail.completed_points(), 2);
+        assert!(detail.is_complete());
+
+        detail.set_high(true);
+        assert!(detail.is_complete());
+        detail.set_status(CalibrationStatus::TwoPoint);
+        assert_eq!(detail.completed_points(), 3);
+        assert!(detail.is_complete());
+
+        detail.set_low(false);
+        assert!(!detail.is_complete());
+    }
+
+    #[test]
+    fn single_point_calibration_detail_is_complete() {
+        let mut detail = CalibrationDetail::with(CalibrationStatus::OnePoint);
+        detail.set_dry(true);
+        detail.set_single(true);
+        assert!(detail.is_complete());
+        assert_eq!(detail.completed_points(), 2);
+    }
+
+    #[test]
+    fn parses_response_kind_by_prefix() {
+        assert_eq!(
+            ResponseKind::parse("?CAL,2").unwrap(),
+            ResponseKind::Calibration(CalibrationStatus::TwoPoint)
+        );
+        assert_eq!(
+            ResponseKind::parse("?K,1.0").unwrap(),
+            ResponseKind::ProbeType(ProbeType::One)
+        );
+        assert_eq!(
+            ResponseKind::parse("?T,19.5").unwrap(),
+            ResponseKind::Compensation(CompensationValue(19.5))
+        );
+        assert_eq!(
+            ResponseKind::parse("?O,EC,S").unwrap(),
+            ResponseKind::Output(OutputStringStatus::parse("?O,EC,S").unwrap())
+        );
+        assert_eq!(
+            ResponseKind::parse("?TDS,0.54").unwrap(),
+            ResponseKind::TdsFactor(TdsFactor(0.54))
+        );
+        assert_eq!(
+            ResponseKind::parse("?*OK,1").unwrap(),
+            ResponseKind::ResponseCode(ResponseCodeStatus::Enabled)
+        );
+        assert_eq!(
+            ResponseKind::parse("12.5,0.0").unwrap(),
+            ResponseKind::Reading(ProbeReading::TwoParameters(12.5, 0.0))
+        );
+    }
+
+    #[test]
+    fn parses_restart_indicator() {
+        assert_eq!(parse_restart("*RS"), Some(RestartReason::Unknown));
+        assert_eq!(parse_restart("*RS,B\r"), Some(RestartReason::BrownOut));
+        assert_eq!(parse_restart("*RS,X"), Some(RestartReason::Unknown));
+        assert_eq!(parse_restart("?STATUS,B,3.3"), None);
+        assert_eq!(parse_restart("12.5"), None);
+
+        let response = ResponseKind::parse("*RS,B").unwrap();
+        assert_eq!(response, ResponseKind::Restarted(RestartReason::BrownOut));
+        assert_eq!(response.response_type(), ResponseType::Restarted);
+        assert_eq!(
+            ResponseKind::parse("\r*RS\r").unwrap(),
+            ResponseKind::Restarted(RestartReason::Unknown)
+        );
+    }
+
+    #[test]
+    fn parsing_unrecognized_response_kind_yields_error() {
+        assert!(ResponseKind::parse("?L,1").is_err());
+        assert!(ResponseKind::parse("?CAL,7").is_err());
+        assert!(ResponseKind::parse("hello").is_err());
+    }
+
+    #[test]
+    fn query_responses_round_trip_through_debug_and_from_str() {
+        for status in [
+            CalibrationStatus::NotCalibrated,
+            CalibrationStatus::OnePoint,
+            CalibrationStatus::TwoPoint,
+        ]
+        .iter()
+        {
+            let parsed = format!("{:?}", status).parse::<CalibrationStatus>();
+            assert_eq!(parsed.unwrap(), *status);
+        }
+
+        for probe in [ProbeType::PointOne, ProbeType::One, ProbeType::Ten].iter() {
+            let parsed = format!("{:?}", probe).parse::<ProbeType>();
+            assert_eq!(parsed.unwrap(), *probe);
+        }
+
+        let value = CompensationValue(19.5);
+        let parsed = format!("{:?}", value).parse::<CompensationValue>();
+        assert_eq!(parsed.unwrap(), value);
+
+        let factor = TdsFactor(0.54);
+        let parsed = format!("{:?}", factor).parse::<TdsFactor>();
+        assert_eq!(parsed.unwrap(), factor);
+
+        for output in [
+            OutputStringStatus::new(),
+            OutputStringStatus::new().with_salinity(true),
+            OutputStringStatus::new()
+                .with_conductivity(true)
+                .with_tds(true)
+                .with_salinity(true)
+                .with_specific_gravity(true),
+        ]
+        .iter()
+        {
+            let parsed = format!("{:?}", output).parse::<OutputStringStatus>();
+            assert_eq!(parsed.unwrap(), *output);
+        }
+    }
+
+    #[test]
+    fn parsing_query_response_from_invalid_str_yields_error() {
+        assert!("?CAL,3".parse::<CalibrationStatus>().is_err());
+        assert!("?K,2.0".parse::<ProbeType>().is_err());
+        assert!("?T,".parse::<CompensationValue>().is_err());
+        assert!("?O,XX".parse::<OutputStringStatus>().is_err());
+    }
+
+    #[test]
+    fn parses_reading_with_expected_number_of_values() {
+        let reading = ProbeReading::parse_expecting("12.5", 1).unwrap();
+        assert_eq!(reading, ProbeReading::OneParameter(12.5));
+
+        let reading = ProbeReading::parse_expecting("12.5,6.7,0.0,1.0", 4).unwrap();
+        assert_eq!(reading, ProbeReading::FourParameters(12.5, 6.7, 0.0, 1.0));
+
+        let status = OutputStringStatus::parse("?O,EC,TDS").unwrap();
+        let reading = ProbeReading::parse_expecting("12.5,6.7", status.enabled_count());
+        assert_eq!(reading.unwrap(), ProbeReading::TwoParameters(12.5, 6.7));
+    }
+
+    #[test]
+    fn parsing_reading_with_unexpected_number_of_values_yields_error() {
+        assert!(ProbeReading::parse_expecting("12.5", 2).is_err());
+        assert!(ProbeReading::parse_expecting("12.5,6.7,0.0", 2).is_err());
+        assert!(ProbeReading::parse_expecting("12.5,,0.0", 3).is_err());
+    }
+
+    #[test]
+    fn strips_response_framing() {
+        assert_eq!(strip_framing("?CAL,2").unwrap(), "?CAL,2");
+        assert_eq!(strip_framing("").unwrap(), "");
+        assert_eq!(strip_framing("*OK").unwrap(), "");
+        assert_eq!(strip_framing("*OK\r").unwrap(), "");
+        assert_eq!(strip_framing("12.5,0.0\r*OK").unwrap(), "12.5,0.0");
+        assert_eq!(strip_framing("*OK,?K,1.0").unwrap(), "?K,1.0");
+
+        let err = strip_framing("*ER").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+        let err = strip_framing("*ER\r").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+    }
+
+    #[test]
+    fn parses_framed_response_kind() {
+        assert_eq!(ResponseKind::parse("*OK").unwrap(), ResponseKind::Ack);
+        assert_eq!(ResponseKind::parse("").unwrap(), ResponseKind::Ack);
+        assert_eq!(
+            ResponseKind::parse("?CAL,1\r*OK").unwrap(),
+            ResponseKind::Calibration(CalibrationStatus::OnePoint)
+        );
+        assert_eq!(
+            ResponseKind::parse("12.5\r*OK").unwrap(),
+            ResponseKind::Reading(ProbeReading::OneParameter(12.5))
+        );
+        assert_eq!(
+            ResponseKind::parse("*ER").unwrap_err().kind(),
+            ErrorKind::DeviceErrorResponse
+        );
+    }
+
+    #[test]
+    fn parses_many_concatenated_responses() {
+        let responses = parse_many("?CAL,2\r12.x\r1413.0,706.5\r");
+        assert_eq!(responses.len(), 3);
+        assert_eq!(
+            *responses[0].as_ref().unwrap(),
+            ResponseKind::Calibration(CalibrationStatus::TwoPoint)
+        );
+        assert_eq!(
+            responses[1].as_ref().unwrap_err().kind(),
+            ErrorKind::ResponseParse
+        );
+        assert_eq!(
+            *responses[2].as_ref().unwrap(),
+            ResponseKind::Reading(ProbeReading::TwoParameters(1413.0, 706.5))
+        );
+    }
+
+    #[test]
+    fn parses_many_framed_responses() {
+        let responses = parse_many("?K,1.0\r*OK\r*OK\r*ER\r12.5\r\n*OK\r");
+        let kinds: Vec<ErrorKind> = responses
+            .iter()
+            .filter_map(|response| response.as_ref().err().map(|err| err.kind()))
+            .collect();
+        assert_eq!(kinds, vec![ErrorKind::DeviceErrorResponse]);
+        let parsed: Vec<ResponseKind> = responses.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ResponseKind::ProbeType(ProbeType::One),
+                ResponseKind::Ack,
+                ResponseKind::Reading(ProbeReading::OneParameter(12.5)),
+            ]
+        );
+        assert!(parse_many("\r\n").is_empty());
+    }
+
+    #[test]
+    fn parses_response_code_status() {
+        let status = ResponseCodeStatus::parse("?*OK,1").unwrap();
+        assert_eq!(status, ResponseCodeStatus::Enabled);
+        assert_eq!(format!("{:?}", status), "?*OK,1");
+
+        let status = "?*OK,0".parse::<ResponseCodeStatus>().unwrap();
+        assert_eq!(status, ResponseCodeStatus::Disabled);
+        assert_eq!(format!("{}", status), "disabled");
+    }
+
+    #[test]
+    fn parsing_invalid_response_code_status_yields_error() {
+        assert!(ResponseCodeStatus::parse("?*OK,").is_err());
+        assert!(ResponseCodeStatus::parse("?*OK,2").is_err());
+        assert!(ResponseCodeStatus::parse("?*OK,1,0").is_err());
+        assert!(ResponseCodeStatus::parse("*OK").is_err());
+    }
+
+    #[cfg(all(feature = "chrono", feature = "serde"))]
+    #[test]
+    fn serializes_timestamped_reading_to_json() {
+        use chrono::TimeZone;
+
+        let reading = TimestampedReading {
+            timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 0).unwrap(),
+            reading: ProbeReading::TwoParameters(12.5, 6.7),
+            status: OutputStringStatus::new()
+                .with_conductivity(true)
+                .with_tds(true),
+            context: ReadingContext::new(),
+        };
+        let json = serde_json::to_string(&reading).unwrap();
+        assert_eq!(
+            json,
+            "{\"timestamp\":\"2018-03-14T12:30:00Z\",\
+             \"reading\":{\"TwoParameters\":[12.5,6.7]},\
+             \"status\":{\"electric_conductivity\":\"On\",\
+             \"total_dissolved_solids\":\"On\",\"salinity\":\"Off\",\
+             \"specific_gravity\":\"Off\"}}"
+        );
+        let parsed: TimestampedReading = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, reading);
+    }
+
+    #[cfg(all(feature = "chrono", feature = "serde"))]
+    #[test]
+    fn round_trips_timestamped_reading_with_context() {
+        use chrono::TimeZone;
+
+        let reading = TimestampedReading {
+            timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 0).unwrap(),
+            reading: ProbeReading::OneParameter(12.5),
+            status: OutputStringStatus::new().with_conductivity(true),
+            context: ReadingContext::new(),
+        }
+        .with_context("location", "pond 3")
+        .with_context("humidity", 61.5);
+        let json = serde_json::to_string(&reading).unwrap();
+        assert!(json.ends_with(",\"context\":{\"humidity\":61.5,\"location\":\"pond 3\"}}"));
+
+        let parsed: TimestampedReading = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, reading);
+        assert_eq!(
+            parsed.context.get("humidity"),
+            Some(&ContextValue::Number(61.5))
+        );
+        assert_eq!(
+            parsed.context.get("location"),
+            Some(&ContextValue::Text("pond 3".to_string()))
+        );
+    }
+
+    #[cfg(all(feature = "std", feature = "chrono", not(feature = "serde")))]
+    #[test]
+    fn logs_timestamped_readings_as_json_lines() {
+        use chrono::TimeZone;
+
+        let status = OutputStringStatus::new()
+            .with_conductivity(true)
+            .with_tds(true);
+        let readings = [
+            TimestampedReading {
+                timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 0).unwrap(),
+                reading: ProbeReading::TwoParameters(12.5, 6.7),
+                status,
+                context: ReadingContext::new(),
+            },
+            TimestampedReading {
+                timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 10).unwrap(),
+                reading: ProbeReading::TwoParameters(12.75, 6.8),
+                status,
+                context: ReadingContext::new(),
+            }
+            .with_context("location", "pond \"3\"")
+            .with_context("humidity", 61.5),
+        ];
+
+        let mut logger = JsonLinesLogger::new(Vec::new());
+        for reading in readings.iter() {
+            logger.log(reading).unwrap();
+        }
+        let log = String::from_utf8(logger.into_inner()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"timestamp":"2018-03-14T12:30:00Z","reading":{"EC":12.5,"TDS":6.7}}"#
+        );
+
+        for (line, reading) in lines.iter().zip(readings.iter()) {
+            let parsed: serde_json::Value = serde_json::from_str(line).unwrap();
+            let timestamp = parsed["timestamp"].as_str().unwrap();
+            assert_eq!(
+                DateTime::parse_from_rfc3339(timestamp).unwrap(),
+                reading.timestamp
+            );
+            assert_eq!(parsed["reading"]["EC"].as_f64(), reading.reading.get(0));
+            assert_eq!(parsed["reading"]["TDS"].as_f64(), reading.reading.get(1));
+        }
+        let parsed: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed["context"]["location"], "pond \"3\"");
+        assert_eq!(parsed["context"]["humidity"], 61.5);
+    }
+
+    #[cfg(all(feature = "std", feature = "chrono", feature = "serde"))]
+    #[test]
+    fn logged_json_lines_round_trip_to_equal_readings() {
+        use chrono::TimeZone;
+
+        let readings = [
+            TimestampedReading {
+                timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 0).unwrap(),
+                reading: ProbeReading::TwoParameters(12.5, 6.7),
+                status: OutputStringStatus::new()
+                    .with_conductivity(true)
+                    .with_tds(true),
+                context: ReadingContext::new(),
+            },
+            TimestampedReading {
+                timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 10).unwrap(),
+                reading: ProbeReading::OneParameter(12.75),
+                status: OutputStringStatus::new().with_conductivity(true),
+                context: ReadingContext::new(),
+            }
+            .with_context("location", "pond \"3\"")
+            .with_context("humidity", 61.5),
+        ];
+
+        let mut logger = JsonLinesLogger::new(Vec::new());
+        for reading in readings.iter() {
+            logger.log(reading).unwrap();
+        }
+        let log = String::from_utf8(logger.into_inner()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], serde_json::to_string(&readings[0]).unwrap());
+        for (line, reading) in lines.iter().zip(readings.iter()) {
+            let parsed: TimestampedReading = serde_json::from_str(line).unwrap();
+            assert_eq!(&parsed, reading);
+        }
+    }
+
+    #[cfg(all(feature = "std", feature = "chrono"))]
+    #[test]
+    fn json_lines_logger_propagates_write_errors() {
+        use chrono::TimeZone;
+        use std::io::{self, Write};
+
+        struct FullDisk;
+
+        impl Write for FullDisk {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let reading = TimestampedReading {
+            timestamp: Utc.with_ymd_and_hms(2018, 3, 14, 12, 30, 0).unwrap(),
+            reading: ProbeReading::OneParameter(12.5),
+            status: OutputStringStatus::new().with_conductivity(true),
+            context: ReadingContext::new(),
+        };
+        let mut logger = JsonLinesLogger::new(FullDisk);
+        let err = logger.log(&reading).unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+    }
+
+    #[test]
+    fn keeps_reading_context_entries_by_key() {
+        let mut context = ReadingContext::new();
+        assert!(context.is_empty());
+        context.insert("pressure", 1013.25);
+        context.insert("location", "tank A");
+        assert_eq!(context.insert("pressure", 1012.0), Some(1013.25.into()));
+        assert_eq!(context.len(), 2);
+        let keys: Vec<&str> = context.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["location", "pressure"]);
+        assert_eq!(context.remove("location"), Some("tank A".into()));
+        assert_eq!(context.get("location"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_output_string_status_with_defaults() {
+        let status: OutputStringStatus = serde_json::from_str(r#"{"salinity":"On"}"#).unwrap();
+        assert_eq!(status, OutputStringStatus::new().with_salinity(true));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn assembles_exported_calibration() {
+        let mut export = ExportedCalibration::new();
+        for line in ["596F75206172", "6520616D617A", "696E"].iter() {
+            let done = export.push(Exported::parse(line).unwrap()).unwrap();
+            assert!(!done);
+        }
+        assert!(export.push(Exported::parse("*DONE").unwrap()).unwrap());
+        assert!(export.is_complete());
+        assert_eq!(export.lines().len(), 3);
+
+        let info = ExportedInfo {
+            lines: 3,
+            total_bytes: 28,
+        };
+        assert_eq!(
+            export.finish(&info).unwrap(),
+            vec!["596F75206172", "6520616D617A", "696E"]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn mismatched_exported_calibration_yields_error() {
+        let mut export = ExportedCalibration::new();
+        for line in ["596F75206172", "6520616D617A", "696E", "*DONE"].iter() {
+            export.push(Exported::parse(line).unwrap()).unwrap();
+        }
+        assert!(export.push(Exported::Done).is_err());
+
+        let info = ExportedInfo {
+            lines: 4,
+            total_bytes: 28,
+        };
+        let err = export.clone().finish(&info).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseParse);
+
+        let mut incomplete = ExportedCalibration::new();
+        incomplete
+            .push(Exported::ExportString("596F75206172".to_string()))
+            .unwrap();
+        let info = ExportedInfo {
+            lines: 1,
+            total_bytes: 12,
+        };
+        assert!(incomplete.finish(&info).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parses_firmware_version_from_device_info() {
+        let info = DeviceInfo::parse("?I,EC,2.10").unwrap();
+        assert_eq!(firmware_version(&info), Some((2, 10)));
+        assert!(firmware_supports_rt(&info));
+
+        let info = DeviceInfo::parse("?I,EC,1.96").unwrap();
+        assert_eq!(firmware_version(&info), Some((1, 96)));
+        assert!(!firmware_supports_rt(&info));
+
+        let info = DeviceInfo::parse("?I,EC,2.16").unwrap();
+        assert!(firmware_supports_rt(&info));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn unreadable_firmware_version_is_none() {
+        let info = DeviceInfo::parse("?I,EC,beta").unwrap();
+        assert_eq!(firmware_version(&info), None);
+        assert!(!firmware_supports_rt(&info));
+
+        let info = DeviceInfo::parse("?I,EC,2.1.3").unwrap();
+        assert_eq!(firmware_version(&info), None);
+    }
+
+    #[test]
+    fn orders_probe_types_by_cell_constant() {
+        assert!(ProbeType::PointOne < ProbeType::One);
+        assert!(ProbeType::One < ProbeType::Ten);
+
+        let mut probes = vec![ProbeType::Ten, ProbeType::PointOne, ProbeType::One];
+        probes.sort();
+        assert_eq!(
+            probes,
+            vec![ProbeType::PointOne, ProbeType::One, ProbeType::Ten]
+        );
+        assert!(probes
+            .windows(2)
+            .all(|pair| pair[0].cell_constant() < pair[1].cell_constant()));
+        assert_eq!(probes.iter().max(), Some(&ProbeType::Ten));
+    }
+
+    #[test]
+    fn default_configuration_types_are_empty() {
+        assert_eq!(OutputStringStatus::default(), OutputStringStatus::new());
+        assert_eq!(ParameterStatus::default(), ParameterStatus::Off);
+
+        let status = OutputStringStatus {
+            salinity: ParameterStatus::On,
+            ..Default::default()
+        };
+        assert_eq!(status.to_string(), "S");
+
+        let detail = CalibrationDetail::default();
+        assert_eq!(detail.status, CalibrationStatus::NotCalibrated);
+        assert_eq!(detail.completed_points(), 0);
+    }
+
+    #[test]
+    fn toggles_and_converts_parameter_status() {
+        assert_eq!(ParameterStatus::On.toggle(), ParameterStatus::Off);
+        assert_eq!(ParameterStatus::Off.toggle(), ParameterStatus::On);
+        assert_eq!(ParameterStatus::On.toggle().toggle(), ParameterStatus::On);
+
+        assert_eq!(ParameterStatus::from(true), ParameterStatus::On);
+        assert_eq!(ParameterStatus::from(false), ParameterStatus::Off);
+        assert!(bool::from(ParameterStatus::On));
+        assert!(!bool::from(ParameterStatus::Off));
+
+        let enabled: bool = ParameterStatus::Off.toggle().into();
+        assert!(enabled);
+    }
+
+    #[test]
+    fn parses_padded_responses_leniently() {
+        assert!(CalibrationStatus::parse(" ?CAL,1\r").is_err());
+        assert_eq!(
+            CalibrationStatus::parse_lenient(" ?CAL,1\r").unwrap(),
+            CalibrationStatus::OnePoint
+        );
+        assert_eq!(
+            ProbeType::parse_lenient("?K,10.0\r").unwrap(),
+            ProbeType::Ten
+        );
+        assert_eq!(
+            CompensationValue::parse_lenient("  ?T,19.5 \r\n").unwrap(),
+            CompensationValue(19.5)
+        );
+        assert_eq!(
+            ProbeReading::parse_lenient(" 12.5,6.7\r").unwrap(),
+            ProbeReading::TwoParameters(12.5, 6.7)
+        );
+        assert_eq!(
+            ResponseKind::parse_lenient(" ?*OK,1\r").unwrap(),
+            ResponseKind::ResponseCode(ResponseCodeStatus::Enabled)
+        );
+        assert!(CalibrationStatus::parse_lenient(" ?CAL, 1\r").is_err());
+    }
+
+    #[test]
+    fn flags_implausible_specific_gravity() {
+        assert_eq!(
+            ProbeMetric::SpecificGravity(1.0).validity(),
+            MetricValidity::Sentinel
+        );
+        assert_eq!(
+            ProbeMetric::SpecificGravity(1.001).validity(),
+            MetricValidity::Valid
+        );
+        assert_eq!(
+            ProbeMetric::SpecificGravity(1.3).validity(),
+            MetricValidity::Valid
+        );
+        assert_eq!(
+            ProbeMetric::SpecificGravity(0.999).validity(),
+            MetricValidity::OutOfRange
+        );
+        assert_eq!(
+            ProbeMetric::SpecificGravity(1.301).validity(),
+            MetricValidity::OutOfRange
+        );
+        assert_eq!(
+            ProbeMetric::SpecificGravity(f64::NAN).validity(),
+            MetricValidity::OutOfRange
+        );
+        assert_eq!(ProbeMetric::Salinity(1.0).validity(), MetricValidity::Valid);
+    }
+
+    #[test]
+    fn plausible_value_is_only_valid_values() {
+        assert_eq!(ProbeMetric::SpecificGravity(1.0).plausible_value(), None);
+        assert_eq!(
+            ProbeMetric::SpecificGravity(1.025).plausible_value(),
+            Some(1.025)
+        );
+        assert_eq!(ProbeMetric::SpecificGravity(1.301).plausible_value(), None);
+        assert_eq!(ProbeMetric::Salinity(0.5).plausible_value(), Some(0.5));
+    }
+
+    #[test]
+    fn converts_celsius_temperatures() {
+        assert_eq!(Celsius::from(19.5), Celsius(19.5));
+        assert_eq!(f64::from(Celsius(19.5)), 19.5);
+        assert_eq!(
+            Celsius::from(CompensationValue::from_fahrenheit(212.0)),
+            Celsius(100.0)
+        );
+        assert_eq!(Celsius(19.5).to_string(), "19.500 °C");
+    }
+
+    #[test]
+    fn classifies_probe_type_by_cell_constant() {
+        assert_eq!(probe_type_from_cell_constant(0.1), ProbeType::PointOne);
+        assert_eq!(probe_type_from_cell_constant(0.09), ProbeType::PointOne);
+        assert_eq!(probe_type_from_cell_constant(0.3), ProbeType::PointOne);
+        assert_eq!(probe_type_from_cell_constant(0.35), ProbeType::One);
+        assert_eq!(probe_type_from_cell_constant(1.0), ProbeType::One);
+        assert_eq!(probe_type_from_cell_constant(1.05), ProbeType::One);
+        assert_eq!(probe_type_from_cell_constant(3.0), ProbeType::One);
+        assert_eq!(probe_type_from_cell_constant(3.2), ProbeType::Ten);
+        assert_eq!(probe_type_from_cell_constant(10.0), ProbeType::Ten);
+        assert_eq!(probe_type_from_cell_constant(25.0), ProbeType::Ten);
+        assert_eq!(probe_type_from_cell_constant(0.0), ProbeType::PointOne);
+        assert_eq!(probe_type_from_cell_constant(-1.0), ProbeType::PointOne);
+        assert_eq!(probe_type_from_cell_constant(f64::NAN), ProbeType::PointOne);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn checks_supply_voltage_of_device_status() {
+        let status = |vcc_voltage| DeviceStatus {
+            restart_reason: RestartReason::PoweredOff,
+            vcc_voltage,
+        };
+        assert!(is_voltage_healthy(&status(3.3)));
+        assert!(is_voltage_healthy(&status(5.02)));
+        assert!(!is_voltage_healthy(&status(2.8)));
+        assert!(!is_voltage_healthy(&status(6.0)));
+
+        assert!(voltage_within(&status(4.8), 4.75, 5.25));
+        assert!(!voltage_within(&status(4.5), 4.75, 5.25));
+    }
+
+    #[test]
+    fn detects_calibration_status_advances() {
+        use self::CalibrationStatus::*;
+
+        assert!(OnePoint.advanced_from(&NotCalibrated));
+        assert!(TwoPoint.advanced_from(&OnePoint));
+        assert!(TwoPoint.advanced_from(&NotCalibrated));
+
+        assert!(!NotCalibrated.advanced_from(&NotCalibrated));
+        assert!(!OnePoint.advanced_from(&OnePoint));
+        assert!(!TwoPoint.advanced_from(&TwoPoint));
+
+        assert!(!NotCalibrated.advanced_from(&OnePoint));
+        assert!(!OnePoint.advanced_from(&TwoPoint));
+    }
+
+    #[test]
+    fn parses_raw_reading() {
+        let reading = RawReading::parse("1413.00,763.02").unwrap();
+        assert_eq!(reading.raw, "1413.00,763.02");
+        assert_eq!(reading.parsed, ProbeReading::TwoParameters(1413.0, 763.02));
+        assert!(RawReading::parse("1413.00,x").is_err());
+    }
+
+    #[test]
+    fn ack_response_displays_ok() {
+        assert_eq!(Ack.to_string(), "OK");
+        assert_eq!(ResponseKind::from(Ack), ResponseKind::Ack);
+        assert_eq!(<Ack as TypedResponse>::RESPONSE_TYPE, ResponseType::Ack);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn converts_ack_to_and_from_response_status() {
+        let ack = Ack::from(ResponseStatus::Ack);
+        assert_eq!(ack, Ack);
+        assert_eq!(ResponseStatus::from(ack), ResponseStatus::Ack);
+    }
+
+    #[test]
+    fn displays_any_response() {
+        let responses = [
+            AnyResponse::Ack,
+            AnyResponse::parse("?CAL,2").unwrap(),
+            AnyResponse::parse("?K,10.0").unwrap(),
+            AnyResponse::parse("?T,19.5").unwrap(),
+            AnyResponse::parse("?O,EC,S").unwrap(),
+            AnyResponse::parse("12.5,0.01").unwrap(),
+        ];
+        let displayed: Vec<String> = responses.iter().map(|r| r.to_string()).collect();
+        let expected: Vec<String> = vec![
+            "OK".to_string(),
+            CalibrationStatus::TwoPoint.to_string(),
+            "10.0".to_string(),
+            "19.500".to_string(),
+            "EC,S".to_string(),
+            ProbeReading::TwoParameters(12.5, 0.01).to_string(),
+        ];
+        assert_eq!(displayed, expected);
+    }
+
+    #[test]
+    fn computes_delta_between_readings() {
+        let previous = ProbeReading::TwoParameters(1200.0, 650.0);
+        let current = ProbeReading::TwoParameters(1250.5, 640.0);
+        assert_eq!(
+            current.delta(&previous).unwrap(),
+            ProbeReading::TwoParameters(50.5, -10.0)
+        );
+        assert_eq!(
+            ProbeReading::OneParameter(12.5)
+                .delta(&ProbeReading::OneParameter(12.5))
+                .unwrap(),
+            ProbeReading::OneParameter(0.0)
+        );
+        assert_eq!(
+            ProbeReading::None.delta(&ProbeReading::None).unwrap(),
+            ProbeReading::None
+        );
+    }
+
+    #[test]
+    fn compares_float_bearing_responses_within_epsilon() {
+        let value = CompensationValue(25.0);
+        assert!(value.approx_eq(&CompensationValue(25.0005), 0.001));
+        assert!(!value.approx_eq(&CompensationValue(25.01), 0.001));
+
+        let metric = ProbeMetric::ElectricConductivity(1413.0);
+        assert!(metric.approx_eq(&ProbeMetric::ElectricConductivity(1412.6), 0.5));
+        assert!(!metric.approx_eq(&ProbeMetric::ElectricConductivity(1412.0), 0.5));
+        assert!(!metric.approx_eq(&ProbeMetric::TotalDissolvedSolids(1413.0), 0.5));
+
+        let reading = ProbeReading::TwoParameters(1413.0, 706.5);
+        assert!(reading.approx_eq(&ProbeReading::TwoParameters(1413.2, 706.4), 0.5));
+        assert!(!reading.approx_eq(&ProbeReading::TwoParameters(1413.2, 705.0), 0.5));
+        assert!(!reading.approx_eq(&ProbeReading::OneParameter(1413.0), 0.5));
+        assert!(ProbeReading::None.approx_eq(&ProbeReading::None, 0.0));
+    }
+
+    #[test]
+    fn delta_between_readings_of_different_arity_yields_error() {
+        let err = ProbeReading::OneParameter(12.5)
+            .delta(&ProbeReading::TwoParameters(12.5, 6.7))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseParse);
+    }
+
+    #[test]
+    fn displays_reading_with_precision() {
+        let reading = ProbeReading::ThreeParameters(12.500000001, 6.75, 0.0123);
+        assert_eq!(reading.display_with_precision(1), "12.5,6.8,0.0");
+        assert_eq!(reading.display_with_precision(2), "12.50,6.75,0.01");
+        assert_eq!(
+            reading.display_with_precision(DEFAULT_READING_PRECISION),
+            "12.500,6.750,0.012"
+        );
+        assert_eq!(ProbeReading::None.display_with_precision(3), "none");
+    }
+
+    #[test]
+    fn writes_reading_as_json() {
+        let status = OutputStringStatus::parse("?O,EC,TDS,S,SG").unwrap();
+        let reading = ProbeReading::FourParameters(50000.0, 25000.5, 32.74, 1.024);
+        assert_eq!(
+            reading.to_json(&status),
+            r#"{"EC":50000,"TDS":25000.5,"S":32.74,"SG":1.024}"#
+        );
+
+        let status = OutputStringStatus::parse("?O,TDS").unwrap();
+        assert_eq!(
+            ProbeReading::OneParameter(0.0).to_json(&status),
+            r#"{"TDS":0}"#
+        );
+        assert_eq!(
+            ProbeReading::OneParameter(f64::NAN).to_json(&status),
+            r#"{"TDS":null}"#
+        );
+        assert_eq!(ProbeReading::None.to_json(&status), "{}");
+        assert_eq!(ProbeReading::None.to_json(&OutputStringStatus::new()), "{}");
+    }
+
+    #[test]
+    fn response_kinds_report_their_type() {
+        assert_eq!(ResponseKind::Ack.response_type(), ResponseType::Ack);
+        assert_eq!(
+            ResponseKind::parse("?CAL,1").unwrap().response_type(),
+            ResponseType::Calibration
+        );
+        assert_eq!(
+            ResponseKind::parse("12.5").unwrap().response_type(),
+            <ProbeReading as TypedResponse>::RESPONSE_TYPE
+        );
+    }
+
+    #[test]
+    fn reports_datasheet_measurement_range_of_each_probe() {
+        assert_eq!(ProbeType::PointOne.measurement_range(), (0.07, 50_000.0));
+        assert_eq!(ProbeType::One.measurement_range(), (5.0, 200_000.0));
+        assert_eq!(ProbeType::Ten.measurement_range(), (10.0, 1_000_000.0));
+    }
+
+    #[test]
+    fn classifies_conductivity_against_probe_range() {
+        for probe in [ProbeType::PointOne, ProbeType::One, ProbeType::Ten].iter() {
+            let (min, max) = probe.measurement_range();
+            assert_eq!(probe.classify(min), ReadingQuality::Ok);
+            assert_eq!(probe.classify(max), ReadingQuality::Ok);
+            assert_eq!(probe.classify(min * 0.9), ReadingQuality::BelowRange);
+            assert_eq!(probe.classify(max * 1.1), ReadingQuality::AboveRange);
+        }
+        assert_eq!(ProbeType::PointOne.classify(1413.0), ReadingQuality::Ok);
+        assert_eq!(
+            ProbeType::PointOne.classify(80_000.0),
+            ReadingQuality::AboveRange
+        );
+        assert_eq!(ProbeType::Ten.classify(5.0), ReadingQuality::BelowRange);
+    }
 }
@@ -0,0 +1,465 @@
+//! Handle on an EC EZO chip that applies settings shared by every command
+//! it runs.
+//!
+#[cfg(feature = "embedded-hal")]
+use super::command::{hal_write, run_common_hal, run_dyn_hal_delayed};
+use super::command::{
+    probe_type_command, run_common_on, run_dyn_delayed, run_dyn_delayed_on, Command, DeviceAddress,
+    DynCommand, EcCommand, Factory, OutputConfigure, OutputParameter, OutputState,
+    ProtocolLockState,
+};
+use super::errors::ProtocolLocked;
+use super::response::{AnyResponse, OutputStringStatus, ProbeType, ProtocolLockStatus};
+use super::transport::EzoTransport;
+use super::{ErrorKind, EzoError};
+
+use failure::{Fail, ResultExt};
+
+use i2cdev::linux::LinuxI2CDevice;
+
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::blocking::i2c::{Read, Write};
+
+/// How long to wait for the chip to process a command, relative to the
+/// datasheet's worst-case delays.
+///
+/// On a well-behaved 400kHz bus the chip is often ready sooner than the
+/// datasheet says. Shorter delays raise throughput, but a chip that isn't
+/// done yet answers with `ErrorKind::PendingResponse`, so profiles other
+/// than `Conservative` trade reliability for speed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DelayProfile {
+    /// The datasheet's delays, unchanged.
+    #[default]
+    Conservative,
+    /// Three quarters of the datasheet's delays.
+    Balanced,
+    /// Half of the datasheet's delays.
+    Aggressive,
+}
+
+impl DelayProfile {
+    /// Factor applied to every command delay.
+    pub fn factor(&self) -> f64 {
+        match *self {
+            DelayProfile::Conservative => 1.0,
+            DelayProfile::Balanced => 0.75,
+            DelayProfile::Aggressive => 0.5,
+        }
+    }
+
+    /// Scales a delay, in milliseconds, rounding up.
+    pub fn scale(&self, delay_ms: u64) -> u64 {
+        (delay_ms as f64 * self.factor()).ceil() as u64
+    }
+}
+
+/// Last-known settings of the chip, as written through an `EcSensor`.
+///
+/// Settings are kept in the chip's EEPROM, which wears with every write.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SettingsCache {
+    pub probe: Option<ProbeType>,
+    pub output: Option<OutputStringStatus>,
+}
+
+/// EC EZO chip on a bus device `I`.
+///
+/// For a `LinuxI2CDevice`, `addr` should be the address the device was
+/// opened with; it's only used when talking over `embedded-hal`.
+#[derive(Debug)]
+pub struct EcSensor<I> {
+    dev: I,
+    addr: u8,
+    delay_profile: DelayProfile,
+    cache: Option<SettingsCache>,
+}
+
+impl<I> EcSensor<I> {
+    /// Handle on the chip at `addr`, using the `Conservative` delay profile.
+    pub fn new(dev: I, addr: u8) -> EcSensor<I> {
+        EcSensor {
+            dev,
+            addr,
+            delay_profile: DelayProfile::default(),
+            cache: None,
+        }
+    }
+
+    /// Sets the delay profile used for every command run on this sensor.
+    pub fn with_delay_profile(mut self, profile: DelayProfile) -> EcSensor<I> {
+        self.delay_profile = profile;
+        self
+    }
+
+    pub fn delay_profile(&self) -> DelayProfile {
+        self.delay_profile
+    }
+
+    pub fn set_delay_profile(&mut self, profile: DelayProfile) {
+        self.delay_profile = profile;
+    }
+
+    pub fn address(&self) -> u8 {
+        self.addr
+    }
+
+    /// Remembers the settings written through this sensor, and skips
+    /// setters that wouldn't change them, sparing the chip's EEPROM.
+    ///
+    /// Settings changed behind the sensor's back, e.g. by another host on
+    /// the bus, go unnoticed; call `invalidate_cache` after those.
+    pub fn with_settings_cache(mut self) -> EcSensor<I> {
+        self.cache = Some(SettingsCache::default());
+        self
+    }
+
+    /// Last-known settings, or `None` when caching is off.
+    pub fn settings_cache(&self) -> Option<&SettingsCache> {
+        self.cache.as_ref()
+    }
+
+    /// Forgets the last-known settings, so the next setters are written.
+    pub fn invalidate_cache(&mut self) {
+        if let Some(cache) = self.cache.as_mut() {
+            *cache = SettingsCache::default();
+        }
+    }
+
+    /// Delay this sensor waits for the response to `cmd`, in milliseconds.
+    pub fn delay_for<C: EcCommand>(&self, cmd: &C) -> u64 {
+        self.delay_profile.scale(cmd.get_delay())
+    }
+
+    /// Releases the bus device.
+    pub fn into_inner(self) -> I {
+        self.dev
+    }
+}
+
+impl EcSensor<LinuxI2CDevice> {
+    /// Runs `cmd`, waiting the delay scaled by the sensor's profile.
+    ///
+    /// When the cached probe type can't produce an output parameter that
+    /// `cmd` enables, it is refused before it's sent; see
+    /// `OutputConfigure::validate_for_probe`.
+    pub fn run<C>(&mut self, cmd: C) -> Result<C::Response, EzoError>
+    where
+        C: EcCommand<Error = EzoError>,
+    {
+        check_output_for_probe(&self.cache, &cmd.get_command_string())?;
+        let delay = self.delay_for(&cmd);
+        cmd.with_delay(delay).run(&mut self.dev)
+    }
+
+    /// Like `run`, through dynamic dispatch. See `command::run_dyn`.
+    pub fn run_dyn(&mut self, cmd: &dyn DynCommand) -> Result<AnyResponse, EzoError> {
+        check_output_for_probe(&self.cache, &cmd.command_string())?;
+        run_dyn_delayed(&mut self.dev, cmd, self.delay_profile.scale(cmd.delay()))
+    }
+
+    /// Sets the probe type, unless the cache shows it's already set.
+    pub fn set_probe_type(&mut self, probe: ProbeType) -> Result<(), EzoError> {
+        let EcSensor {
+            dev,
+            delay_profile,
+            cache,
+            ..
+        } = self;
+        set_probe_type_with(cache, probe, |cmd| {
+            run_dyn_delayed(dev, cmd, delay_profile.scale(cmd.delay()))
+        })
+    }
+
+    /// Enables and disables output parameters to match `output`, writing
+    /// only the ones that differ. Without a cached output, the current one
+    /// is queried first.
+    ///
+    /// When the cached probe type can't produce an enabled parameter, the
+    /// output is refused; see `OutputParameter::validate_for_probe`.
+    pub fn set_output(&mut self, output: &OutputStringStatus) -> Result<(), EzoError> {
+        let EcSensor {
+            dev,
+            delay_profile,
+            cache,
+            ..
+        } = self;
+        set_output_with(cache, output, |cmd| {
+            run_dyn_delayed(dev, cmd, delay_profile.scale(cmd.delay()))
+        })
+    }
+
+    /// Restores the chip's factory settings, and invalidates the cache.
+    ///
+    /// The protocol lock is checked first, and when it's on this fails with
+    /// `ErrorKind::DeviceErrorResponse`, caused by a `ProtocolLocked`.
+    pub fn factory_reset(&mut self) -> Result<(), EzoError> {
+        self.ensure_unlocked(&Factory)?;
+        self.invalidate_cache();
+        Factory.run(&mut self.dev)
+    }
+
+    /// Moves the chip to the I2C address `addr`, and follows it there. The
+    /// cache is invalidated, as the chip restarts.
+    ///
+    /// Like `factory_reset`, this is refused while the protocol lock is on.
+    pub fn set_address(&mut self, addr: u8) -> Result<(), EzoError> {
+        let cmd = DeviceAddress(u16::from(addr));
+        self.ensure_unlocked(&cmd)?;
+        self.invalidate_cache();
+        cmd.run(&mut self.dev)?;
+        self.dev
+            .set_slave_address(u16::from(addr))
+            .context(ErrorKind::I2CWrite)?;
+        self.addr = addr;
+        Ok(())
+    }
+
+    /// Fails with a `ProtocolLocked` cause for `cmd` when the lock is on.
+    fn ensure_unlocked<C: Command>(&mut self, cmd: &C) -> Result<(), EzoError> {
+        let status = ProtocolLockState.run(&mut self.dev)?;
+        check_unlocked(status, &cmd.get_command_string())
+    }
+}
+
+impl<T: EzoTransport> EcSensor<T> {
+    /// Like `run`, over any `EzoTransport`.
+    pub fn run_on<C>(&mut self, cmd: C) -> Result<C::Response, EzoError>
+    where
+        C: EcCommand<Error = EzoError>,
+    {
+        check_output_for_probe(&self.cache, &cmd.get_command_string())?;
+        let delay = self.delay_for(&cmd);
+        cmd.with_delay(delay).run_on(&mut self.dev)
+    }
+
+    /// Like `run_dyn`, over any `EzoTransport`.
+    pub fn run_dyn_on(&mut self, cmd: &dyn DynCommand) -> Result<AnyResponse, EzoError> {
+        check_output_for_probe(&self.cache, &cmd.command_string())?;
+        let delay = self.delay_profile.scale(cmd.delay());
+        run_dyn_delayed_on(&mut self.dev, cmd, delay)
+    }
+
+    /// Like `set_probe_type`, over any `EzoTransport`.
+    pub fn set_probe_type_on(&mut self, probe: ProbeType) -> Result<(), EzoError> {
+        let EcSensor {
+            dev,
+            delay_profile,
+            cache,
+            ..
+        } = self;
+        set_probe_type_with(cache, probe, |cmd| {
+            run_dyn_delayed_on(dev, cmd, delay_profile.scale(cmd.delay()))
+        })
+    }
+
+    /// Like `set_output`, over any `EzoTransport`.
+    pub fn set_output_on(&mut self, output: &OutputStringStatus) -> Result<(), EzoError> {
+        let EcSensor {
+            dev,
+            delay_profile,
+            cache,
+            ..
+        } = self;
+        set_output_with(cache, output, |cmd| {
+            run_dyn_delayed_on(dev, cmd, delay_profile.scale(cmd.delay()))
+        })
+    }
+
+    /// Like `factory_reset`, over any `EzoTransport`.
+    pub fn factory_reset_on(&mut self) -> Result<(), EzoError> {
+        let cmd = Factory.get_command_string();
+        let status = run_common_on(&mut self.dev, &ProtocolLockState, ProtocolLockStatus::parse)?;
+        check_unlocked(status, &cmd)?;
+        self.invalidate_cache();
+        EzoTransport::write(&mut self.dev, &cmd)
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<I: Write + Read> EcSensor<I> {
+    /// Like `run`, over an `embedded-hal` I2C bus.
+    pub fn run_hal<C>(&mut self, cmd: C) -> Result<C::Response, EzoError>
+    where
+        C: EcCommand<Error = EzoError>,
+    {
+        check_output_for_probe(&self.cache, &cmd.get_command_string())?;
+        let delay = self.delay_for(&cmd);
+        cmd.with_delay(delay).run_hal(&mut self.dev, self.addr)
+    }
+
+    /// Like `run_dyn`, over an `embedded-hal` I2C bus.
+    pub fn run_dyn_hal(&mut self, cmd: &dyn DynCommand) -> Result<AnyResponse, EzoError> {
+        check_output_for_probe(&self.cache, &cmd.command_string())?;
+        let delay = self.delay_profile.scale(cmd.delay());
+        run_dyn_hal_delayed(&mut self.dev, self.addr, cmd, delay)
+    }
+
+    /// Like `set_probe_type`, over an `embedded-hal` I2C bus.
+    pub fn set_probe_type_hal(&mut self, probe: ProbeType) -> Result<(), EzoError> {
+        let EcSensor {
+            dev,
+            addr,
+            delay_profile,
+            cache,
+        } = self;
+        set_probe_type_with(cache, probe, |cmd| {
+            run_dyn_hal_delayed(dev, *addr, cmd, delay_profile.scale(cmd.delay()))
+        })
+    }
+
+    /// Like `set_output`, over an `embedded-hal` I2C bus.
+    pub fn set_output_hal(&mut self, output: &OutputStringStatus) -> Result<(), EzoError> {
+        let EcSensor {
+            dev,
+            addr,
+            delay_profile,
+            cache,
+        } = self;
+        set_output_with(cache, output, |cmd| {
+            run_dyn_hal_delayed(dev, *addr, cmd, delay_profile.scale(cmd.delay()))
+        })
+    }
+
+    /// Like `factory_reset`, over an `embedded-hal` I2C bus.
+    pub fn factory_reset_hal(&mut self) -> Result<(), EzoError> {
+        let cmd = Factory.get_command_string();
+        self.ensure_unlocked_hal(&cmd)?;
+        self.invalidate_cache();
+        hal_write(&mut self.dev, self.addr, &cmd)
+    }
+
+    /// Like `set_address`, over an `embedded-hal` I2C bus.
+    pub fn set_address_hal(&mut self, addr: u8) -> Result<(), EzoError> {
+        let cmd = DeviceAddress(u16::from(addr)).get_command_string();
+        self.ensure_unlocked_hal(&cmd)?;
+        self.invalidate_cache();
+        hal_write(&mut self.dev, self.addr, &cmd)?;
+        self.addr = addr;
+        Ok(())
+    }
+
+    /// Like `ensure_unlocked`, over an `embedded-hal` I2C bus.
+    fn ensure_unlocked_hal(&mut self, command: &str) -> Result<(), EzoError> {
+        let status = run_common_hal(
+            &mut self.dev,
+            self.addr,
+            &ProtocolLockState,
+            ProtocolLockStatus::parse,
+        )?;
+        check_unlocked(status, command)
+    }
+}
+
+/// Refuses `command` when the protocol lock is on, as the chip would
+/// silently ignore it.
+fn check_unlocked(status: ProtocolLockStatus, command: &str) -> Result<(), EzoError> {
+    match status {
+        ProtocolLockStatus::Off => Ok(()),
+        ProtocolLockStatus::On => Err(ProtocolLocked {
+            command: command.to_string(),
+        }
+        .context(ErrorKind::DeviceErrorResponse)
+        .into()),
+    }
+}
+
+/// Refuses `command` when it enables an output parameter that the cached
+/// probe type can't produce, e.g. `O,SG,1` with a K 0.1 probe.
+fn check_output_for_probe(cache: &Option<SettingsCache>, command: &str) -> Result<(), EzoError> {
+    match (*cache, command.parse::<OutputConfigure>()) {
+        (
+            Some(SettingsCache {
+                probe: Some(probe), ..
+            }),
+            Ok(cmd),
+        ) => cmd.validate_for_probe(probe),
+        _ => Ok(()),
+    }
+}
+
+fn set_probe_type_with<F>(
+    cache: &mut Option<SettingsCache>,
+    probe: ProbeType,
+    mut run: F,
+) -> Result<(), EzoError>
+where
+    F: FnMut(&dyn DynCommand) -> Result<AnyResponse, EzoError>,
+{
+    if let Some(SettingsCache {
+        probe: Some(cached),
+        ..
+    }) = *cache
+    {
+        if cached == probe {
+            return Ok(());
+        }
+    }
+    run(probe_type_command(probe))?;
+    if let Some(cache) = cache.as_mut() {
+        cache.probe = Some(probe);
+    }
+    Ok(())
+}
+
+fn set_output_with<F>(
+    cache: &mut Option<SettingsCache>,
+    output: &OutputStringStatus,
+    mut run: F,
+) -> Result<(), EzoError>
+where
+    F: FnMut(&dyn DynCommand) -> Result<AnyResponse, EzoError>,
+{
+    if let Some(SettingsCache {
+        probe: Some(probe), ..
+    }) = *cache
+    {
+        if output.specific_gravity.into() {
+            OutputParameter::SpecificGravity.validate_for_probe(probe)?;
+        }
+    }
+    let current = match cache.and_then(|cache| cache.output) {
+        Some(current) => current,
+        None => match run(&OutputState)? {
+            AnyResponse::Output(current) => current,
+            _ => return Err(ErrorKind::ResponseParse)?,
+        },
+    };
+    for cmd in current.diff_commands(output) {
+        run(&cmd)?;
+    }
+    if let Some(cache) = cache.as_mut() {
+        cache.output = Some(*output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{CalibrationDry, Reading, TemperatureCompensation};
+    use crate::response::Celsius;
+
+    #[test]
+    fn conservative_profile_keeps_datasheet_delays() {
+        assert_eq!(DelayProfile::default(), DelayProfile::Conservative);
+        assert_eq!(DelayProfile::Conservative.scale(600), 600);
+        assert_eq!(DelayProfile::Conservative.scale(300), 300);
+    }
+
+    #[test]
+    fn profiles_scale_command_delays() {
+        assert_eq!(DelayProfile::Balanced.scale(600), 450);
+        assert_eq!(DelayProfile::Aggressive.scale(600), 300);
+        assert_eq!(DelayProfile::Aggressive.scale(1), 1);
+        assert_eq!(DelayProfile::Aggressive.scale(0), 0);
+
+        let sensor = EcSensor::new((), 0x64).with_delay_profile(DelayProfile::Aggressive);
+        assert_eq!(sensor.delay_for(&Reading), Reading.get_delay() / 2);
+        assert_eq!(sensor.delay_for(&CalibrationDry), 400);
+        assert_eq!(
+            sensor.delay_for(&TemperatureCompensation(Celsius(25.0))),
+            TemperatureCompensation(Celsius(25.0)).get_delay() / 2
+        );
+    }
+}
@@ -0,0 +1,854 @@
+//! Mock I2C device for exercising command round-trips without hardware.
+//!
+//! `Command::run` is tied to `LinuxI2CDevice`, so the mock is an
+//! `EzoTransport`, for `EcCommand::run_on` and the other `_on` runners,
+//! which skips the command delays. It also implements the `embedded-hal` I2C
+//! traits, for the `_hal` runners, which wait the delays out.
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::transport::EzoTransport;
+use super::{ErrorKind, EzoError};
+
+use embedded_hal::blocking::i2c::{Read, Write};
+
+/// Error returned by `MockEzoDevice` when no response has been queued.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MockError;
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no response queued on mock device")
+    }
+}
+
+/// Fake EZO chip that records written commands and replays queued responses.
+#[derive(Clone, Debug, Default)]
+pub struct MockEzoDevice {
+    written: Vec<Vec<u8>>,
+    responses: VecDeque<Vec<u8>>,
+}
+
+impl MockEzoDevice {
+    pub fn new() -> MockEzoDevice {
+        MockEzoDevice::default()
+    }
+
+    /// Queues the raw bytes returned by the next read, response code included.
+    pub fn queue_bytes(&mut self, bytes: &[u8]) {
+        self.responses.push_back(bytes.to_vec());
+    }
+
+    /// Queues a successful response carrying `data`, e.g. `"12.5,0.0"`.
+    pub fn queue_response(&mut self, data: &str) {
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(data.as_bytes());
+        bytes.push(0);
+        self.responses.push_back(bytes);
+    }
+
+    /// Queues a response made only of the given response code.
+    pub fn queue_code(&mut self, code: u8) {
+        self.responses.push_back(vec![code, 0]);
+    }
+
+    /// Raw bytes of every command written so far, nul terminator included.
+    pub fn written(&self) -> &[Vec<u8>] {
+        &self.written
+    }
+
+    /// Every command written so far, as strings.
+    pub fn written_commands(&self) -> Vec<String> {
+        self.written
+            .iter()
+            .map(|bytes| {
+                let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+                String::from_utf8_lossy(&bytes[..end]).into_owned()
+            })
+            .collect()
+    }
+
+    /// Number of queued responses that have not been read yet.
+    pub fn pending_responses(&self) -> usize {
+        self.responses.len()
+    }
+}
+
+impl Write for MockEzoDevice {
+    type Error = MockError;
+
+    fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), MockError> {
+        self.written.push(bytes.to_vec());
+        Ok(())
+    }
+}
+
+impl Read for MockEzoDevice {
+    type Error = MockError;
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), MockError> {
+        let response = self.responses.pop_front().ok_or(MockError)?;
+        for b in buffer.iter_mut() {
+            *b = 0;
+        }
+        let len = response.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&response[..len]);
+        Ok(())
+    }
+}
+
+impl EzoTransport for MockEzoDevice {
+    fn write(&mut self, command: &str) -> Result<(), EzoError> {
+        let mut bytes = command.as_bytes().to_vec();
+        bytes.push(0);
+        self.written.push(bytes);
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), EzoError> {
+        Read::read(self, 0, buffer).map_err(|_| ErrorKind::I2CRead)?;
+        Ok(())
+    }
+
+    fn delay(&mut self, _ms: u64) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{
+        read_raw_on, run_dyn_on, run_sequence_on, snapshot_on, wake_on, CalibrationDry,
+        CalibrationState, CalibrationWizard, Command, DynCommand, EcCommand, Find, LedOff, LedOn,
+        OutputConfigure, OutputEnableSpecificGravity, OutputParameter, ProbeTypeOne,
+        ProbeTypePointOne, ProbeTypeState, RawCommand, Reading, ReadingStream,
+        ReadingWithCompensation, SensorConfig, TemperatureCompensation, VerifiedSetter,
+        WizardState,
+    };
+    use crate::errors::{
+        device_error, failed_command, protocol_locked, setting_mismatch, truncated_response,
+        unsupported_output,
+    };
+    use crate::response::{
+        Ack, AnyResponse, CalibrationStatus, Celsius, CompensationValue, OutputStringStatus,
+        ProbeReading, ProbeType, ResponseStatus,
+    };
+    use crate::sensor::{DelayProfile, EcSensor};
+    use crate::ErrorKind;
+    use failure::Fail;
+    use std::time::Duration;
+
+    const ADDR: u8 = 0x64;
+
+    #[test]
+    fn runs_reading_over_mock_device() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("12.5,0.0");
+
+        let reading = Reading.run_hal(&mut dev, ADDR).unwrap();
+        assert_eq!(reading, ProbeReading::TwoParameters(12.5, 0.0));
+        assert_eq!(dev.written(), &[b"R\0".to_vec()]);
+        assert_eq!(dev.pending_responses(), 0);
+    }
+
+    #[test]
+    fn runs_commands_over_mock_transport() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("12.5,0.0");
+        dev.queue_response("?K,1.0");
+
+        let started = std::time::Instant::now();
+        assert_eq!(
+            Reading.run_on(&mut dev).unwrap(),
+            ProbeReading::TwoParameters(12.5, 0.0)
+        );
+        assert_eq!(ProbeTypeState.run_on(&mut dev).unwrap(), ProbeType::One);
+        assert!(started.elapsed() < Duration::from_millis(600));
+        assert_eq!(dev.written_commands(), ["R", "K,?"]);
+
+        let error = Reading.run_on(&mut dev).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::I2CRead);
+    }
+
+    #[test]
+    fn runs_ack_and_query_commands_over_mock_device() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?CAL,1");
+
+        assert_eq!(
+            CalibrationDry.run_on(&mut dev).unwrap(),
+            ResponseStatus::Ack
+        );
+        assert_eq!(
+            CalibrationState.run_on(&mut dev).unwrap(),
+            CalibrationStatus::OnePoint
+        );
+        assert_eq!(dev.written_commands(), vec!["CAL,DRY", "CAL,?"]);
+    }
+
+    #[test]
+    fn blinks_and_stops_find_over_mock_device() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("");
+
+        assert_eq!(Find.run_on(&mut dev).unwrap(), ResponseStatus::Ack);
+        assert_eq!(LedOn.run_on(&mut dev).unwrap(), ResponseStatus::Ack);
+        assert_eq!(
+            dev.written_commands(),
+            vec![Find.get_command_string(), LedOn.get_command_string()]
+        );
+
+        dev.queue_code(2);
+        let err = LedOff.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+    }
+
+    #[test]
+    fn runs_raw_commands_over_mock_device() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("?Name,pond3\r*OK");
+        dev.queue_code(255);
+
+        let query = RawCommand::new("Name,?").unwrap();
+        assert_eq!(query.run_on(&mut dev).unwrap(), "?Name,pond3");
+
+        let setter = RawCommand::new("Name,pond3")
+            .unwrap()
+            .with_response_delay(Duration::from_millis(0))
+            .with_expected_response(false);
+        assert_eq!(setter.run_on(&mut dev).unwrap(), "");
+        assert_eq!(dev.written_commands(), vec!["Name,?", "Name,pond3"]);
+
+        dev.queue_code(255);
+        let err = query.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NoDataExpectedResponse);
+        assert_eq!(failed_command(&err).unwrap(), "Name,?");
+    }
+
+    #[test]
+    fn ack_command_response_prints_ok() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("*OK");
+
+        let ack = Ack::from(CalibrationDry.run_on(&mut dev).unwrap());
+        assert_eq!(ack.to_string(), "OK");
+    }
+
+    #[test]
+    fn mock_device_surfaces_error_codes_and_empty_queue() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_code(2);
+
+        let err = Reading.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+
+        let err = Reading.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::I2CRead);
+    }
+
+    #[test]
+    fn runs_sequence_of_commands_over_mock_device() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("");
+        dev.queue_response("?CAL,2");
+
+        let result = run_sequence_on(
+            &mut dev,
+            &[
+                &ProbeTypeOne,
+                &TemperatureCompensation(Celsius(19.5)),
+                &CalibrationState,
+            ],
+        );
+        assert!(result.is_ok());
+        assert_eq!(dev.written_commands(), vec!["K,1.0", "T,19.500", "CAL,?"]);
+    }
+
+    #[test]
+    fn sequence_stops_at_first_failed_command() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_code(2);
+        dev.queue_response("");
+
+        let err = run_sequence_on(
+            &mut dev,
+            &[
+                &ProbeTypeOne,
+                &TemperatureCompensation(Celsius(19.5)),
+                &CalibrationState,
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(err.index, 1);
+        assert_eq!(err.command, "T,19.500");
+        assert_eq!(err.error.kind(), ErrorKind::DeviceErrorResponse);
+        assert_eq!(dev.written_commands(), vec!["K,1.0", "T,19.500"]);
+        assert_eq!(dev.pending_responses(), 1);
+    }
+
+    #[test]
+    fn replays_cloned_sequence_of_boxed_commands() {
+        let init: Vec<Box<dyn DynCommand>> = vec![
+            Box::new(ProbeTypeOne),
+            Box::new(TemperatureCompensation(Celsius(19.5))),
+            Box::new(Reading.with_delay(300)),
+        ];
+        let replay = init.clone();
+        assert_eq!(replay.len(), 3);
+        assert_eq!(replay[2].delay(), 300);
+
+        let mut dev = MockEzoDevice::new();
+        for _ in 0..6 {
+            dev.queue_response("");
+        }
+        for cmds in [&init, &replay].iter() {
+            let cmds: Vec<&dyn DynCommand> = cmds.iter().map(|cmd| cmd.as_ref()).collect();
+            run_sequence_on(&mut dev, &cmds).unwrap();
+        }
+        assert_eq!(
+            dev.written_commands(),
+            vec!["K,1.0", "T,19.500", "R", "K,1.0", "T,19.500", "R"]
+        );
+    }
+
+    #[test]
+    fn captures_reading_with_compensation_temperature() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("12.5");
+        dev.queue_response("?T,19.5");
+
+        let captured = ReadingWithCompensation::capture_on(&mut dev).unwrap();
+        assert_eq!(
+            captured.into_pair().unwrap(),
+            (ProbeReading::OneParameter(12.5), CompensationValue(19.5))
+        );
+        assert_eq!(dev.written_commands(), vec!["R", "T,?"]);
+    }
+
+    #[test]
+    fn keeps_reading_when_compensation_query_fails() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("12.5");
+        dev.queue_code(2);
+
+        let captured = ReadingWithCompensation::capture_on(&mut dev).unwrap();
+        assert_eq!(captured.reading, ProbeReading::OneParameter(12.5));
+        assert_eq!(
+            captured.compensation.unwrap_err().kind(),
+            ErrorKind::DeviceErrorResponse
+        );
+
+        let mut dev = MockEzoDevice::new();
+        dev.queue_code(254);
+        let err = ReadingWithCompensation::capture_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PendingResponse);
+        assert_eq!(dev.written_commands(), vec!["R"]);
+    }
+
+    #[test]
+    fn truncated_reading_yields_partial_response() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_bytes(b"\x0112.5,6.7,0.\xff\xff\xff");
+
+        let err = Reading.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MalformedResponse);
+        let truncated = truncated_response(&err).unwrap();
+        assert_eq!(truncated.partial, "12.5,6.7,0.");
+    }
+
+    #[test]
+    fn wakes_sleeping_device() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_bytes(b"\xff");
+        dev.queue_response("?K,1.0");
+
+        wake_on(&mut dev).unwrap();
+        assert_eq!(dev.written_commands(), vec!["K,?", "K,?"]);
+
+        let mut dev = MockEzoDevice::new();
+        dev.queue_bytes(b"\xff");
+        dev.queue_code(254);
+        let err = wake_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PendingResponse);
+    }
+
+    #[test]
+    fn runs_command_returning_raw_response() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("?CAL,3 \r");
+        dev.queue_code(2);
+
+        assert_eq!(CalibrationState.run_raw_on(&mut dev).unwrap(), "?CAL,3");
+        let err = CalibrationState.run_raw_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+        assert_eq!(dev.written_commands(), vec!["CAL,?", "CAL,?"]);
+    }
+
+    #[test]
+    fn errors_name_the_failed_command() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("?CAL,7");
+
+        let err = CalibrationState.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseParse);
+        assert_eq!(failed_command(&err).unwrap(), "CAL,?");
+        let cause = Fail::cause(&err).unwrap();
+        assert_eq!(cause.to_string(), "could not parse response to 'CAL,?'");
+
+        dev.queue_code(254);
+        let err = Reading.run_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PendingResponse);
+        assert_eq!(failed_command(&err).unwrap(), "R");
+        assert!(device_error(&err).unwrap().is_pending());
+    }
+
+    #[test]
+    fn streams_readings_and_keeps_going_after_errors() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("12.5");
+        dev.queue_response("12.x");
+        dev.queue_response("12.7");
+
+        let mut stream = ReadingStream::new(&mut dev, ADDR, Duration::from_millis(0));
+        assert_eq!(
+            stream.next_reading().unwrap(),
+            ProbeReading::OneParameter(12.5)
+        );
+        let err = stream.next_reading().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseParse);
+        let item = stream.next().unwrap();
+        assert_eq!(item.unwrap(), ProbeReading::OneParameter(12.7));
+
+        assert_eq!(dev.written_commands(), vec!["R", "R", "R"]);
+        assert_eq!(dev.pending_responses(), 0);
+    }
+
+    #[test]
+    fn runs_dynamic_commands_into_matching_responses() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?CAL,1");
+        dev.queue_response("?K,1.0");
+        dev.queue_response("12.5,6.7");
+
+        let cmds: Vec<Box<dyn DynCommand>> = vec![
+            Box::new(CalibrationDry),
+            Box::new(CalibrationState),
+            Box::new(ProbeTypeState),
+            Box::new(Reading),
+        ];
+        let responses: Vec<AnyResponse> = cmds
+            .iter()
+            .map(|cmd| run_dyn_on(&mut dev, cmd.as_ref()).unwrap())
+            .collect();
+        assert_eq!(
+            responses,
+            vec![
+                AnyResponse::Ack,
+                AnyResponse::Calibration(CalibrationStatus::OnePoint),
+                AnyResponse::ProbeType(ProbeType::One),
+                AnyResponse::Reading(ProbeReading::TwoParameters(12.5, 6.7)),
+            ]
+        );
+    }
+
+    fn sensor_config() -> SensorConfig {
+        SensorConfig {
+            name: Some("tank-1".to_string()),
+            probe: ProbeType::One,
+            output: OutputStringStatus::new()
+                .with_conductivity(true)
+                .with_tds(true),
+            tds_factor: 0.54,
+            temperature: Celsius(19.5),
+        }
+    }
+
+    #[test]
+    fn applies_and_verifies_sensor_config() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?K,1.0");
+        dev.queue_response("?O,EC");
+        dev.queue_response("");
+        dev.queue_response("?O,EC,TDS");
+        dev.queue_response("");
+        dev.queue_response("?TDS,0.54");
+        dev.queue_response("");
+        dev.queue_response("?T,19.5");
+
+        sensor_config().apply_on(&mut dev).unwrap();
+        assert_eq!(
+            dev.written_commands(),
+            vec!["K,1.0", "K,?", "O,?", "O,TDS,1", "O,?", "TDS,0.54", "TDS,?", "T,19.500", "T,?"]
+        );
+        assert_eq!(dev.pending_responses(), 0);
+    }
+
+    #[test]
+    fn applying_sensor_config_that_does_not_take_yields_err() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?K,10.0");
+
+        let err = sensor_config().apply_on(&mut dev).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+        let mismatch = setting_mismatch(&err).unwrap();
+        assert_eq!(mismatch.setting, "probe type");
+        assert_eq!(mismatch.expected, "1.0");
+        assert_eq!(mismatch.actual, "10.0");
+        assert_eq!(dev.written_commands(), vec!["K,1.0", "K,?"]);
+    }
+
+    #[test]
+    fn runs_verified_setters() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?T,19.5");
+        dev.queue_response("");
+        dev.queue_response("?K,0.1");
+        dev.queue_response("");
+        dev.queue_response("?O,EC,S");
+
+        TemperatureCompensation(Celsius(19.5))
+            .run_verified_on(&mut dev)
+            .unwrap();
+        ProbeTypePointOne.run_verified_on(&mut dev).unwrap();
+        OutputConfigure {
+            parameter: OutputParameter::Salinity,
+            enable: true,
+        }
+        .run_verified_on(&mut dev)
+        .unwrap();
+        assert_eq!(
+            dev.written_commands(),
+            vec!["T,19.500", "T,?", "K,0.1", "K,?", "O,S,1", "O,?"]
+        );
+    }
+
+    #[test]
+    fn verified_setter_detects_value_that_did_not_stick() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?T,25.0");
+
+        let err = TemperatureCompensation(Celsius(19.5))
+            .run_verified_on(&mut dev)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DeviceErrorResponse);
+        let mismatch = setting_mismatch(&err).unwrap();
+        assert_eq!(mismatch.setting, "temperature compensation");
+        assert_eq!(mismatch.actual, "25.000");
+
+        dev.queue_response("");
+        dev.queue_response("?O,EC");
+        let err = OutputConfigure {
+            parameter: OutputParameter::Tds,
+            enable: true,
+        }
+        .run_verified_on(&mut dev)
+        .unwrap_err();
+        assert_eq!(setting_mismatch(&err).unwrap().expected, "O,TDS,1");
+    }
+
+    #[test]
+    fn runs_commands_through_sensor_with_delay_profile() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("1413.0");
+        let mut sensor = EcSensor::new(dev, ADDR).with_delay_profile(DelayProfile::Balanced);
+
+        assert_eq!(
+            sensor.run_on(Reading).unwrap(),
+            ProbeReading::OneParameter(1413.0)
+        );
+        assert_eq!(sensor.into_inner().written_commands(), vec!["R"]);
+    }
+
+    #[test]
+    fn drives_calibration_wizard_through_every_step() {
+        let mut dev = MockEzoDevice::new();
+        for status in &["?CAL,0", "?CAL,1", "?CAL,2"] {
+            dev.queue_response("");
+            dev.queue_response(status);
+        }
+        dev.queue_response("?CAL,2");
+
+        let mut wizard = CalibrationWizard::new(12_880.0, 80_000.0);
+        let states: Vec<WizardState> = wizard
+            .steps_on(&mut dev)
+            .map(|state| state.unwrap())
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                WizardState::Low,
+                WizardState::High,
+                WizardState::Verify,
+                WizardState::Done,
+            ]
+        );
+        assert_eq!(wizard.state(), WizardState::Done);
+        assert_eq!(
+            dev.written_commands(),
+            vec![
+                "CAL,DRY",
+                "CAL,?",
+                "CAL,LOW,12880.00",
+                "CAL,?",
+                "CAL,HIGH,80000.00",
+                "CAL,?",
+                "CAL,?",
+            ]
+        );
+    }
+
+    #[test]
+    fn calibration_wizard_stops_at_point_that_did_not_register() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?CAL,0");
+        dev.queue_response("");
+        dev.queue_response("?CAL,0");
+
+        let mut wizard = CalibrationWizard::new(12_880.0, 80_000.0);
+        let results: Vec<_> = wizard.steps_on(&mut dev).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(*results[0].as_ref().unwrap(), WizardState::Low);
+        let error = results[1].as_ref().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::DeviceErrorResponse);
+        assert_eq!(setting_mismatch(error).unwrap().setting, "calibration");
+        assert_eq!(wizard.state(), WizardState::Low);
+    }
+
+    #[test]
+    fn settings_cache_suppresses_redundant_writes() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?O,EC");
+        dev.queue_response("");
+        let mut sensor = EcSensor::new(dev, ADDR).with_settings_cache();
+        let output = OutputStringStatus::parse("?O,EC,S").unwrap();
+
+        for _ in 0..3 {
+            sensor.set_probe_type_on(ProbeType::One).unwrap();
+            sensor.set_output_on(&output).unwrap();
+        }
+        let cache = sensor.settings_cache().unwrap();
+        assert_eq!(cache.probe, Some(ProbeType::One));
+        assert_eq!(cache.output, Some(output));
+        assert_eq!(
+            sensor.into_inner().written_commands(),
+            vec!["K,1.0", "O,?", "O,S,1"]
+        );
+    }
+
+    #[test]
+    fn factory_reset_and_address_change_invalidate_settings_cache() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("?PLOCK,0");
+        dev.queue_response("");
+        dev.queue_response("?PLOCK,0");
+        dev.queue_response("");
+        let mut sensor = EcSensor::new(dev, ADDR).with_settings_cache();
+
+        sensor.set_probe_type_on(ProbeType::Ten).unwrap();
+        sensor.factory_reset_on().unwrap();
+        assert_eq!(sensor.settings_cache().unwrap().probe, None);
+        sensor.set_probe_type_on(ProbeType::Ten).unwrap();
+        sensor.set_address_hal(0x65).unwrap();
+        assert_eq!(sensor.address(), 0x65);
+        sensor.set_probe_type_on(ProbeType::Ten).unwrap();
+        assert_eq!(
+            sensor.into_inner().written_commands(),
+            vec!["K,10.0", "PLOCK,?", "FACTORY", "K,10.0", "PLOCK,?", "I2C,101", "K,10.0"]
+        );
+    }
+
+    #[test]
+    fn settings_are_always_written_without_cache() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("");
+        let mut sensor = EcSensor::new(dev, ADDR);
+
+        sensor.set_probe_type_on(ProbeType::One).unwrap();
+        sensor.set_probe_type_on(ProbeType::One).unwrap();
+        assert!(sensor.settings_cache().is_none());
+        assert_eq!(
+            sensor.into_inner().written_commands(),
+            vec!["K,1.0", "K,1.0"]
+        );
+    }
+
+    #[test]
+    fn reads_raw_and_parsed_reading_from_one_command() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("1413.00,763.02");
+        dev.queue_response("1413.00,7x3.02");
+
+        let reading = read_raw_on(&mut dev).unwrap();
+        assert_eq!(reading.raw, "1413.00,763.02");
+        assert_eq!(reading.parsed, ProbeReading::parse(&reading.raw).unwrap());
+
+        let error = read_raw_on(&mut dev).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ResponseParse);
+        assert_eq!(failed_command(&error).unwrap(), "R");
+        assert_eq!(dev.written_commands(), vec!["R", "R"]);
+    }
+
+    #[test]
+    fn locked_device_rejects_destructive_commands() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("?PLOCK,1");
+        dev.queue_response("?PLOCK,1");
+        let mut sensor = EcSensor::new(dev, ADDR);
+
+        let error = sensor.factory_reset_on().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::DeviceErrorResponse);
+        assert_eq!(protocol_locked(&error).unwrap().command, "FACTORY");
+
+        let error = sensor.set_address_hal(0x65).unwrap_err();
+        assert_eq!(protocol_locked(&error).unwrap().command, "I2C,101");
+        assert_eq!(sensor.address(), ADDR);
+        assert_eq!(
+            sensor.into_inner().written_commands(),
+            vec!["PLOCK,?", "PLOCK,?"]
+        );
+    }
+
+    #[test]
+    fn refuses_specific_gravity_output_on_point_one_probe() {
+        let mut config = sensor_config();
+        config.probe = ProbeType::PointOne;
+        config.output = config.output.with_specific_gravity(true);
+        let mut dev = MockEzoDevice::new();
+        let error = config.apply_on(&mut dev).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::CommandParse);
+        assert_eq!(
+            unsupported_output(&error).unwrap().probe,
+            ProbeType::PointOne
+        );
+        assert!(dev.written().is_empty());
+
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("");
+        dev.queue_response("?O,EC");
+        dev.queue_response("");
+        let mut sensor = EcSensor::new(dev, ADDR).with_settings_cache();
+        let output = OutputStringStatus::new()
+            .with_conductivity(true)
+            .with_specific_gravity(true);
+        sensor.set_probe_type_on(ProbeType::PointOne).unwrap();
+        assert!(sensor.set_output_on(&output).is_err());
+        sensor.set_probe_type_on(ProbeType::Ten).unwrap();
+        sensor.set_output_on(&output).unwrap();
+        assert_eq!(
+            sensor.into_inner().written_commands(),
+            vec!["K,0.1", "K,10.0", "O,?", "O,SG,1"]
+        );
+    }
+
+    #[test]
+    fn sensor_refuses_commands_enabling_specific_gravity_on_point_one_probe() {
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        dev.queue_response("");
+        let mut sensor = EcSensor::new(dev, ADDR).with_settings_cache();
+        sensor.set_probe_type_on(ProbeType::PointOne).unwrap();
+
+        let error = sensor.run_on(OutputEnableSpecificGravity).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::CommandParse);
+        assert_eq!(
+            unsupported_output(&error).unwrap().probe,
+            ProbeType::PointOne
+        );
+        let raw = RawCommand::new("o,sg,1").unwrap();
+        assert!(unsupported_output(&sensor.run_on(raw).unwrap_err()).is_some());
+        let enable = OutputConfigure {
+            parameter: OutputParameter::SpecificGravity,
+            enable: true,
+        };
+        assert!(unsupported_output(&sensor.run_dyn_on(&enable).unwrap_err()).is_some());
+        sensor
+            .run_on(OutputConfigure {
+                parameter: OutputParameter::SpecificGravity,
+                enable: false,
+            })
+            .unwrap();
+        assert_eq!(
+            sensor.into_inner().written_commands(),
+            vec!["K,0.1", "O,SG,0"]
+        );
+
+        let mut dev = MockEzoDevice::new();
+        dev.queue_response("");
+        let mut sensor = EcSensor::new(dev, ADDR);
+        sensor.run_on(OutputEnableSpecificGravity).unwrap();
+        assert_eq!(sensor.into_inner().written_commands(), vec!["O,SG,1"]);
+    }
+
+    fn queue_snapshot(dev: &mut MockEzoDevice) {
+        dev.queue_response("?I,EC,2.10");
+        dev.queue_response("?STATUS,P,5.038");
+        dev.queue_response("?CAL,2");
+        dev.queue_response("?K,1.0");
+        dev.queue_response("?O,EC,TDS");
+        dev.queue_code(2);
+        dev.queue_response("1413.00,763.02");
+    }
+
+    #[test]
+    fn snapshot_records_failed_queries_per_field() {
+        let mut dev = MockEzoDevice::new();
+        queue_snapshot(&mut dev);
+
+        let snapshot = snapshot_on(&mut dev).unwrap();
+        assert!(!snapshot.is_complete());
+        assert_eq!(snapshot.info.as_ref().unwrap().firmware, "2.10");
+        assert_eq!(snapshot.status.as_ref().unwrap().vcc_voltage, 5.038);
+        assert_eq!(snapshot.calibration, Ok(CalibrationStatus::TwoPoint));
+        assert_eq!(snapshot.probe, Ok(ProbeType::One));
+        assert_eq!(
+            snapshot.output,
+            Ok(OutputStringStatus::parse("?O,EC,TDS").unwrap())
+        );
+        assert!(snapshot.compensation.unwrap_err().contains("'T,?'"));
+        assert_eq!(
+            snapshot.reading,
+            Ok(ProbeReading::TwoParameters(1413.0, 763.02))
+        );
+        assert_eq!(
+            dev.written_commands(),
+            vec!["I", "STATUS", "CAL,?", "K,?", "O,?", "T,?", "R"]
+        );
+    }
+
+    #[test]
+    fn snapshot_of_absent_device_yields_err() {
+        let mut dev = MockEzoDevice::new();
+        let error = snapshot_on(&mut dev).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::I2CRead);
+        assert_eq!(failed_command(&error).unwrap(), "R");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_snapshot_to_json() {
+        let mut dev = MockEzoDevice::new();
+        queue_snapshot(&mut dev);
+        let snapshot = snapshot_on(&mut dev).unwrap();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.starts_with(
+            "{\"info\":{\"Ok\":{\"device\":\"EC\",\"firmware\":\"2.10\"}},\
+             \"status\":{\"Ok\":{\"restart_reason\":\"PoweredOff\",\"vcc_voltage\":5.038}},"
+        ));
+        let parsed: crate::response::DeviceSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+}
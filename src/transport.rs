@@ -0,0 +1,220 @@
+//! Byte-level links to the chip, so that commands can run over buses other
+//! than a `LinuxI2CDevice`, e.g. USB-to-I2C bridges or network-proxied I2C.
+//!
+//! `Command::run`, defined in `ezo_common`, only takes a `LinuxI2CDevice`.
+//! Use `EcCommand::run_on` to run a command over any `EzoTransport`.
+use std::thread;
+use std::time::Duration;
+
+use super::{ErrorKind, EzoError};
+
+use failure::ResultExt;
+
+use ezo_common::write_to_ezo;
+
+use i2cdev::core::I2CDevice;
+use i2cdev::linux::LinuxI2CDevice;
+
+#[cfg(feature = "embedded-hal")]
+use super::command::hal_write;
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Read, Write},
+};
+
+/// Link to a single EZO EC chip.
+pub trait EzoTransport {
+    /// Sends `command` to the chip, followed by a nul terminator.
+    fn write(&mut self, command: &str) -> Result<(), EzoError>;
+
+    /// Fills `buffer` with the chip's response, response code included.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), EzoError>;
+
+    /// Waits `ms` milliseconds for the chip to process a command.
+    fn delay(&mut self, ms: u64) {
+        thread::sleep(Duration::from_millis(ms));
+    }
+}
+
+impl EzoTransport for LinuxI2CDevice {
+    fn write(&mut self, command: &str) -> Result<(), EzoError> {
+        write_to_ezo(self, command)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), EzoError> {
+        I2CDevice::read(self, buffer).context(ErrorKind::I2CRead)?;
+        Ok(())
+    }
+}
+
+/// `embedded-hal` delay that blocks the current thread.
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug, Default)]
+pub(crate) struct ThreadDelay;
+
+#[cfg(feature = "embedded-hal")]
+impl DelayMs<u32> for ThreadDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        thread::sleep(Duration::from_millis(u64::from(ms)));
+    }
+}
+
+/// Chip at address `addr` on an `embedded-hal` I2C bus.
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug)]
+pub struct HalTransport<'a, I> {
+    dev: &'a mut I,
+    addr: u8,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<'a, I: Write + Read> HalTransport<'a, I> {
+    pub fn new(dev: &'a mut I, addr: u8) -> HalTransport<'a, I> {
+        HalTransport { dev, addr }
+    }
+
+    /// Address of the chip on the bus.
+    pub fn address(&self) -> u8 {
+        self.addr
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<I: Write + Read> EzoTransport for HalTransport<'_, I> {
+    fn write(&mut self, command: &str) -> Result<(), EzoError> {
+        hal_write(self.dev, self.addr, command)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), EzoError> {
+        self.dev
+            .read(self.addr, buffer)
+            .map_err(|_| ErrorKind::I2CRead)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{
+        run_sequence_on, CalibrationDry, CalibrationLow, EcCommand, ProbeTypeOne, Reading,
+    };
+    use crate::response::{ProbeReading, ResponseStatus};
+
+    /// Transport that replays a single response, and records what it's
+    /// asked to do.
+    #[derive(Default)]
+    struct Recorder {
+        log: Vec<String>,
+        response: Vec<u8>,
+    }
+
+    impl EzoTransport for Recorder {
+        fn write(&mut self, command: &str) -> Result<(), EzoError> {
+            self.log.push(format!("write {}", command));
+            Ok(())
+        }
+
+        fn read(&mut self, buffer: &mut [u8]) -> Result<(), EzoError> {
+            self.log.push("read".to_string());
+            let len = self.response.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&self.response[..len]);
+            Ok(())
+        }
+
+        fn delay(&mut self, ms: u64) {
+            self.log.push(format!("delay {}", ms));
+        }
+    }
+
+    #[test]
+    fn runs_commands_over_custom_transport() {
+        let mut transport = Recorder {
+            response: b"\x011413.0\0".to_vec(),
+            ..Recorder::default()
+        };
+        assert_eq!(
+            Reading.run_on(&mut transport).unwrap(),
+            ProbeReading::OneParameter(1413.0)
+        );
+        assert_eq!(transport.log, ["write R", "delay 600", "read"]);
+
+        let mut transport = Recorder {
+            response: b"\x01\0".to_vec(),
+            ..Recorder::default()
+        };
+        assert_eq!(
+            CalibrationDry.run_on(&mut transport).unwrap(),
+            ResponseStatus::Ack
+        );
+        assert_eq!(transport.log, ["write CAL,DRY", "delay 800", "read"]);
+    }
+
+    #[test]
+    fn waits_cooldown_between_sequence_commands() {
+        let mut transport = Recorder {
+            response: b"\x01\0".to_vec(),
+            ..Recorder::default()
+        };
+        run_sequence_on(
+            &mut transport,
+            &[&CalibrationDry, &ProbeTypeOne, &CalibrationLow(1413.0)],
+        )
+        .unwrap();
+        assert_eq!(
+            transport.log,
+            [
+                "write CAL,DRY",
+                "delay 800",
+                "read",
+                "delay 300",
+                "write K,1.0",
+                "delay 600",
+                "read",
+                "write CAL,LOW,1413.00",
+                "delay 800",
+                "read",
+            ]
+        );
+        assert_eq!(Reading.cooldown(), Duration::from_millis(0));
+        assert_eq!(
+            CalibrationDry.with_delay(1000).cooldown(),
+            Duration::from_millis(300)
+        );
+    }
+
+    #[test]
+    fn no_data_response_code_acks_setters_only() {
+        let mut transport = Recorder {
+            response: b"\xff\0".to_vec(),
+            ..Recorder::default()
+        };
+        assert_eq!(
+            CalibrationDry.run_on(&mut transport).unwrap(),
+            ResponseStatus::Ack
+        );
+        assert_eq!(
+            ProbeTypeOne.with_delay(100).run_on(&mut transport).unwrap(),
+            ResponseStatus::Ack
+        );
+        run_sequence_on(&mut transport, &[&ProbeTypeOne]).unwrap();
+
+        let error = Reading.run_on(&mut transport).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NoDataExpectedResponse);
+        let error = run_sequence_on(&mut transport, &[&ProbeTypeOne, &Reading]).unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(error.error.kind(), ErrorKind::NoDataExpectedResponse);
+    }
+
+    #[test]
+    fn names_failed_command_on_custom_transport() {
+        let mut transport = Recorder {
+            response: b"\x02\0".to_vec(),
+            ..Recorder::default()
+        };
+        let error = Reading.run_on(&mut transport).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::DeviceErrorResponse);
+        assert_eq!(crate::errors::failed_command(&error).unwrap(), "R");
+    }
+}